@@ -0,0 +1,443 @@
+//! Archive of deliveries that failed with a retryable error, replayed with the `replay` command
+
+use crate::deliveries::DeliveryStore;
+use crate::error::{Error, Result};
+use crate::github::models::UnverifiedOrigin;
+use crate::http_handler::{self, WebhookRequest};
+use crate::jira::{IssueTracker, JiraRegistry};
+use crate::outcome::Outcome;
+use crate::queue::QueuedDelivery;
+use crate::routing::RoutingConfig;
+use crate::secrets::BoxFuture;
+use crate::types::WebhookSecretMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+#[cfg(feature = "aws-s3")]
+mod s3;
+
+#[cfg(feature = "aws-s3")]
+pub use s3::S3DeliveryArchive;
+
+const ARCHIVE_BUCKET: &str = "ARCHIVE_BUCKET";
+#[cfg(feature = "aws-s3")]
+const ARCHIVE_PREFIX: &str = "ARCHIVE_PREFIX";
+const ARCHIVE_DIR: &str = "ARCHIVE_DIR";
+
+/// A failed delivery with what is needed to replay it
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ArchivedDelivery {
+    pub delivery_id: Option<String>,
+    /// As claimed by the payload, only for filtering
+    pub repository: Option<String>,
+    pub error: String,
+    /// Error code of the response, e.g. `jira_unavailable`
+    pub error_code: String,
+    pub archived_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub delivery: QueuedDelivery,
+}
+
+impl ArchivedDelivery {
+    pub fn new(request: WebhookRequest<'_>, error: &Error, error_code: &str) -> Self {
+        let origin: UnverifiedOrigin = serde_json::from_slice(request.body).unwrap_or_default();
+        Self {
+            delivery_id: request.delivery_id().map(str::to_string),
+            repository: origin.repository.map(|repository| repository.full_name),
+            error: error.to_string(),
+            error_code: error_code.to_string(),
+            archived_at: Utc::now(),
+            delivery: QueuedDelivery::new(request),
+        }
+    }
+
+    /// File name or object key, sorts by time of archiving
+    pub fn key(&self) -> String {
+        let delivery_id: String = self
+            .delivery_id
+            .as_deref()
+            .unwrap_or("unknown")
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        format!(
+            "{}-{delivery_id}.json",
+            self.archived_at.format("%Y%m%dT%H%M%S%.3fZ")
+        )
+    }
+}
+
+/// Storage of failed deliveries
+pub trait DeliveryArchive: Send + Sync {
+    fn store<'a>(&'a self, delivery: &'a ArchivedDelivery) -> BoxFuture<'a, Result<()>>;
+
+    /// All archived deliveries, oldest first
+    fn list(&self) -> BoxFuture<'_, Result<Vec<ArchivedDelivery>>>;
+
+    fn remove<'a>(&'a self, delivery: &'a ArchivedDelivery) -> BoxFuture<'a, Result<()>>;
+}
+
+/// One JSON file per delivery in a local directory
+pub struct FilesystemArchive {
+    dir: PathBuf,
+}
+
+impl FilesystemArchive {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+fn io_error(path: &std::path::Path, error: std::io::Error) -> Error {
+    Error::Archive(format!("{}: {error}", path.display()))
+}
+
+impl DeliveryArchive for FilesystemArchive {
+    fn store<'a>(&'a self, delivery: &'a ArchivedDelivery) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            tokio::fs::create_dir_all(&self.dir)
+                .await
+                .map_err(|e| io_error(&self.dir, e))?;
+            let path = self.dir.join(delivery.key());
+            tokio::fs::write(&path, serde_json::to_vec_pretty(delivery)?)
+                .await
+                .map_err(|e| io_error(&path, e))
+        })
+    }
+
+    fn list(&self) -> BoxFuture<'_, Result<Vec<ArchivedDelivery>>> {
+        Box::pin(async move {
+            let mut paths = Vec::new();
+            let mut entries = match tokio::fs::read_dir(&self.dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+                Err(e) => return Err(io_error(&self.dir, e)),
+            };
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .map_err(|e| io_error(&self.dir, e))?
+            {
+                let path = entry.path();
+                if path
+                    .extension()
+                    .is_some_and(|extension| extension == "json")
+                {
+                    paths.push(path);
+                }
+            }
+            paths.sort();
+
+            let mut deliveries = Vec::with_capacity(paths.len());
+            for path in paths {
+                let content = tokio::fs::read(&path)
+                    .await
+                    .map_err(|e| io_error(&path, e))?;
+                deliveries.push(serde_json::from_slice(&content)?);
+            }
+            Ok(deliveries)
+        })
+    }
+
+    fn remove<'a>(&'a self, delivery: &'a ArchivedDelivery) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let path = self.dir.join(delivery.key());
+            tokio::fs::remove_file(&path)
+                .await
+                .map_err(|e| io_error(&path, e))
+        })
+    }
+}
+
+/// S3 bucket `ARCHIVE_BUCKET` under `ARCHIVE_PREFIX` if set, else the directory `ARCHIVE_DIR`,
+/// failed deliveries are not archived if neither is set
+pub async fn from_env() -> Result<Option<Box<dyn DeliveryArchive>>> {
+    match std::env::var(ARCHIVE_BUCKET) {
+        #[cfg(feature = "aws-s3")]
+        Ok(bucket) => {
+            let prefix =
+                std::env::var(ARCHIVE_PREFIX).unwrap_or_else(|_| "deliveries/".to_string());
+            let aws_config =
+                aws_config::load_defaults(aws_config::BehaviorVersion::v2026_01_12()).await;
+            Ok(Some(Box::new(S3DeliveryArchive::from_env(
+                &aws_config,
+                bucket,
+                prefix,
+            ))))
+        }
+        #[cfg(not(feature = "aws-s3"))]
+        Ok(_) => Err(Error::EnvVarBadValue {
+            env_var_name: ARCHIVE_BUCKET.to_string(),
+        }),
+        Err(_) => Ok(std::env::var(ARCHIVE_DIR)
+            .ok()
+            .map(|dir| Box::new(FilesystemArchive::new(dir)) as Box<dyn DeliveryArchive>)),
+    }
+}
+
+/// Archives the delivery if it failed with a retryable error, failing to archive is only logged
+pub async fn archive_failure(
+    archive: &dyn DeliveryArchive,
+    request: WebhookRequest<'_>,
+    result: &Result<Outcome>,
+) {
+    let Some((error, error_code)) = http_handler::retryable_error(result) else {
+        return;
+    };
+
+    let delivery = ArchivedDelivery::new(request, error, error_code);
+    match archive.store(&delivery).await {
+        Ok(()) => tracing::info!(key = delivery.key(), "Archived failed delivery"),
+        Err(error) => tracing::error!(
+            delivery_id = delivery.delivery_id,
+            ?error,
+            "Failed to archive delivery"
+        ),
+    }
+}
+
+/// Selects the archived deliveries to replay, all criteria have to match
+#[derive(Debug, Default)]
+pub struct ReplayFilter {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub repository: Option<String>,
+    pub error_code: Option<String>,
+}
+
+impl ReplayFilter {
+    pub fn matches(&self, delivery: &ArchivedDelivery) -> bool {
+        self.since.is_none_or(|since| delivery.archived_at >= since)
+            && self.until.is_none_or(|until| delivery.archived_at < until)
+            && self.repository.as_ref().is_none_or(|repository| {
+                delivery
+                    .repository
+                    .as_ref()
+                    .is_some_and(|r| r.eq_ignore_ascii_case(repository))
+            })
+            && self
+                .error_code
+                .as_ref()
+                .is_none_or(|code| &delivery.error_code == code)
+    }
+}
+
+/// Result of every replayed delivery
+#[derive(Default)]
+pub struct ReplayReport(Vec<(String, std::result::Result<(), String>)>);
+
+impl ReplayReport {
+    pub fn passed(&self) -> bool {
+        self.0.iter().all(|(_, result)| result.is_ok())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, result) in &self.0 {
+            match result {
+                Ok(()) => writeln!(f, "OK {key}")?,
+                Err(error) => writeln!(f, "FAIL {key}: {error}")?,
+            }
+        }
+        writeln!(f, "{} deliveries replayed", self.0.len())
+    }
+}
+
+/// Runs the archived deliveries matching the filter through the handler again, deliveries that
+/// succeed are removed from the archive unless in dry run mode
+#[allow(clippy::too_many_arguments)]
+pub async fn replay<T: IssueTracker>(
+    archive: &dyn DeliveryArchive,
+    filter: &ReplayFilter,
+    jira: JiraRegistry<T>,
+    webhook_secrets: &WebhookSecretMap,
+    deliveries: &dyn DeliveryStore,
+    routing: &RoutingConfig,
+    dry_run: bool,
+    skip_signature: bool,
+) -> Result<ReplayReport> {
+    let mut report = ReplayReport::default();
+    for delivery in archive.list().await? {
+        if !filter.matches(&delivery) {
+            continue;
+        }
+
+        let key = delivery.key();
+        let request = match delivery.delivery.to_request() {
+            Ok(request) => request,
+            Err(error) => {
+                report.0.push((key, Err(error.to_string())));
+                continue;
+            }
+        };
+        let request = WebhookRequest::from(&request);
+
+        let result = if skip_signature {
+            http_handler::handle_verified(jira.clone(), deliveries, request, routing, dry_run).await
+        } else {
+            http_handler::function_handler(
+                jira.clone(),
+                webhook_secrets,
+                deliveries,
+                request,
+                routing,
+                dry_run,
+            )
+            .await
+        };
+
+        let error = match &result {
+            Ok(outcome) => outcome.error(),
+            Err(error) => Some(error),
+        };
+        match error {
+            Some(error) => report.0.push((key, Err(error.to_string()))),
+            None => {
+                if !dry_run {
+                    archive.remove(&delivery).await?;
+                }
+                report.0.push((key, Ok(())));
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deliveries::MemoryDeliveryStore;
+    use crate::github::{signed_request, TEST_SECRET as SECRET};
+    use crate::jira::fake::FakeIssueTracker;
+    use crate::routing::DEFAULT_SITE;
+    use crate::types::WebhookSecret;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("github-webhook-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn request(delivery_id: &str, signature: &str) -> http::Request<String> {
+        let body = std::fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/pull_request_opened.json"
+        ))
+        .unwrap();
+        signed_request("pull_request", delivery_id, body, signature)
+    }
+
+    fn archived(delivery_id: &str, signature: &str) -> ArchivedDelivery {
+        let request = request(delivery_id, signature);
+        ArchivedDelivery::new(
+            (&request).into(),
+            &Error::JiraApi("unavailable".to_string()),
+            "jira_error",
+        )
+    }
+
+    #[tokio::test]
+    async fn filesystem_archive_round_trips() {
+        let archive = FilesystemArchive::new(temp_dir("round-trip"));
+        assert!(archive.list().await.unwrap().is_empty());
+
+        let delivery = archived("delivery-1", SECRET);
+        archive.store(&delivery).await.unwrap();
+
+        let listed = archive.list().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].delivery_id.as_deref(), Some("delivery-1"));
+        assert_eq!(listed[0].repository.as_deref(), Some("example-org/widgets"));
+        assert_eq!(listed[0].error_code, "jira_error");
+        assert_eq!(listed[0].delivery, delivery.delivery);
+
+        archive.remove(&listed[0]).await.unwrap();
+        assert!(archive.list().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn filter_matches_all_criteria() {
+        let delivery = archived("delivery-1", SECRET);
+        let now = delivery.archived_at;
+
+        assert!(ReplayFilter::default().matches(&delivery));
+        assert!(ReplayFilter {
+            since: Some(now - chrono::Duration::hours(1)),
+            until: Some(now + chrono::Duration::hours(1)),
+            repository: Some("Example-Org/Widgets".to_string()),
+            error_code: Some("jira_error".to_string()),
+        }
+        .matches(&delivery));
+        assert!(!ReplayFilter {
+            until: Some(now),
+            ..ReplayFilter::default()
+        }
+        .matches(&delivery));
+        assert!(!ReplayFilter {
+            repository: Some("octo-org/other".to_string()),
+            ..ReplayFilter::default()
+        }
+        .matches(&delivery));
+        assert!(!ReplayFilter {
+            error_code: Some("jira_unavailable".to_string()),
+            ..ReplayFilter::default()
+        }
+        .matches(&delivery));
+    }
+
+    #[tokio::test]
+    async fn replay_removes_handled_deliveries() {
+        let archive = FilesystemArchive::new(temp_dir("replay"));
+        archive
+            .store(&archived("delivery-1", SECRET))
+            .await
+            .unwrap();
+        archive
+            .store(&archived("delivery-2", "rotated secret"))
+            .await
+            .unwrap();
+
+        let tracker = FakeIssueTracker::default().with_issue(
+            "ABC-123",
+            Some("# Development Process\n-! Task 1: Create Pull Request\n# Pull Requests"),
+        );
+        let webhook_secrets = WebhookSecret::new(SECRET).unwrap().into();
+        let filter = ReplayFilter::default();
+        let deliveries = MemoryDeliveryStore::default();
+        let routing = RoutingConfig::default();
+        let replay = |skip_signature| {
+            replay(
+                &archive,
+                &filter,
+                JiraRegistry::from_iter([(DEFAULT_SITE.to_string(), tracker.clone())]),
+                &webhook_secrets,
+                &deliveries,
+                &routing,
+                false,
+                skip_signature,
+            )
+        };
+
+        let report = replay(false).await.unwrap();
+        assert_eq!(report.len(), 2);
+        assert!(!report.passed());
+        let remaining = archive.list().await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].delivery_id.as_deref(), Some("delivery-2"));
+
+        assert!(replay(true).await.unwrap().passed());
+        assert!(archive.list().await.unwrap().is_empty());
+    }
+}
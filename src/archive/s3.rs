@@ -0,0 +1,198 @@
+use super::{ArchivedDelivery, DeliveryArchive};
+use crate::error::{Error, Result};
+use crate::secrets::BoxFuture;
+use aws_sdk_s3::primitives::ByteStream;
+
+const S3_ENDPOINT_URL: &str = "S3_ENDPOINT_URL";
+
+/// One JSON object per delivery under a key prefix, e.g. `deliveries/`
+pub struct S3DeliveryArchive {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    prefix: String,
+}
+
+impl S3DeliveryArchive {
+    pub fn new(client: aws_sdk_s3::Client, bucket: String, prefix: String) -> Self {
+        Self {
+            client,
+            bucket,
+            prefix,
+        }
+    }
+
+    /// The endpoint can be overridden with `S3_ENDPOINT_URL`, e.g. for MinIO or LocalStack, path
+    /// style addressing is used then
+    pub fn from_env(aws_config: &aws_config::SdkConfig, bucket: String, prefix: String) -> Self {
+        let mut config = aws_sdk_s3::config::Builder::from(aws_config);
+        if let Ok(url) = std::env::var(S3_ENDPOINT_URL) {
+            config = config.endpoint_url(url).force_path_style(true);
+        }
+        Self::new(
+            aws_sdk_s3::Client::from_conf(config.build()),
+            bucket,
+            prefix,
+        )
+    }
+
+    fn key(&self, delivery: &ArchivedDelivery) -> String {
+        format!("{}{}", self.prefix, delivery.key())
+    }
+}
+
+impl DeliveryArchive for S3DeliveryArchive {
+    fn store<'a>(&'a self, delivery: &'a ArchivedDelivery) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(self.key(delivery))
+                .content_type("application/json")
+                .body(ByteStream::from(serde_json::to_vec_pretty(delivery)?))
+                .send()
+                .await
+                .map_err(|e| Error::S3(Box::new(e.into())))?;
+            Ok(())
+        })
+    }
+
+    fn list(&self) -> BoxFuture<'_, Result<Vec<ArchivedDelivery>>> {
+        Box::pin(async move {
+            let mut keys = Vec::new();
+            let mut pages = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(&self.prefix)
+                .into_paginator()
+                .send();
+            while let Some(page) = pages.next().await {
+                let page = page.map_err(|e| Error::S3(Box::new(e.into())))?;
+                keys.extend(
+                    page.contents()
+                        .iter()
+                        .filter_map(|object| object.key())
+                        .map(str::to_string),
+                );
+            }
+            keys.sort();
+
+            let mut deliveries = Vec::with_capacity(keys.len());
+            for key in keys {
+                let object = self
+                    .client
+                    .get_object()
+                    .bucket(&self.bucket)
+                    .key(&key)
+                    .send()
+                    .await
+                    .map_err(|e| Error::S3(Box::new(e.into())))?;
+                let content = object
+                    .body
+                    .collect()
+                    .await
+                    .map_err(|e| Error::Archive(format!("{key}: {e}")))?;
+                deliveries.push(serde_json::from_slice(&content.into_bytes())?);
+            }
+            Ok(deliveries)
+        })
+    }
+
+    fn remove<'a>(&'a self, delivery: &'a ArchivedDelivery) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.client
+                .delete_object()
+                .bucket(&self.bucket)
+                .key(self.key(delivery))
+                .send()
+                .await
+                .map_err(|e| Error::S3(Box::new(e.into())))?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::test_sdk_config as sdk_config;
+    use wiremock::matchers::{method, path, path_regex, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn archive(server: &MockServer) -> S3DeliveryArchive {
+        let config = aws_sdk_s3::config::Builder::from(&sdk_config(server))
+            .force_path_style(true)
+            .build();
+        S3DeliveryArchive::new(
+            aws_sdk_s3::Client::from_conf(config),
+            "archive".to_string(),
+            "deliveries/".to_string(),
+        )
+    }
+
+    fn delivery() -> ArchivedDelivery {
+        let request = http::Request::builder()
+            .header("X-GitHub-Delivery", "delivery-1")
+            .body(r#"{"repository":{"full_name":"example-org/widgets"}}"#)
+            .unwrap();
+        ArchivedDelivery::new(
+            (&request).into(),
+            &Error::JiraApi("unavailable".to_string()),
+            "jira_error",
+        )
+    }
+
+    #[tokio::test]
+    async fn stores_under_prefix() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path_regex(
+                r"^/archive/deliveries/\d{8}T\d{6}\.\d{3}Z-delivery_1\.json$",
+            ))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        archive(&server).store(&delivery()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn lists_and_reads_objects() {
+        let server = MockServer::start().await;
+        let delivery = delivery();
+        let key = format!("deliveries/{}", delivery.key());
+        Mock::given(method("GET"))
+            .and(path("/archive/"))
+            .and(query_param("list-type", "2"))
+            .and(query_param("prefix", "deliveries/"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Name>archive</Name>
+  <Prefix>deliveries/</Prefix>
+  <KeyCount>1</KeyCount>
+  <IsTruncated>false</IsTruncated>
+  <Contents><Key>{key}</Key></Contents>
+</ListBucketResult>"#
+                ),
+                "application/xml",
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/archive/{key}")))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw(serde_json::to_vec(&delivery).unwrap(), "application/json"),
+            )
+            .mount(&server)
+            .await;
+
+        let listed = archive(&server).list().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].delivery_id.as_deref(), Some("delivery-1"));
+        assert_eq!(listed[0].repository.as_deref(), Some("example-org/widgets"));
+    }
+}
@@ -0,0 +1,432 @@
+//! Links the pull requests a repository had before the webhook was set up to their issues, by
+//! running every pull request through the same path as its latest event
+
+use crate::error::Error;
+use crate::event_handler::handle_pull_request_event;
+use crate::github::client::GitHubClient;
+use crate::github::models::{PullRequestPayload, Repository};
+use crate::jira::{IssueTracker, JiraRegistry};
+use crate::outcome::{IssueResult, Outcome};
+use crate::routing::RoutingConfig;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Which pull requests of which repository to backfill and how
+#[derive(Debug)]
+pub struct BackfillOptions {
+    pub repository: String,
+    /// `open`, `closed` or `all`
+    pub state: String,
+    /// Only pull requests created at or after
+    pub since: Option<DateTime<Utc>>,
+    /// Only pull requests created before
+    pub until: Option<DateTime<Utc>>,
+    /// Pull requests handled at the same time
+    pub concurrency: usize,
+    /// File remembering the pull requests done, a run with the same file continues after them
+    pub checkpoint: Option<PathBuf>,
+    pub dry_run: bool,
+}
+
+impl Default for BackfillOptions {
+    fn default() -> Self {
+        Self {
+            repository: String::new(),
+            state: "all".to_string(),
+            since: None,
+            until: None,
+            concurrency: DEFAULT_CONCURRENCY,
+            checkpoint: None,
+            dry_run: true,
+        }
+    }
+}
+
+/// The newest pull request of the repository before which every pull request was handled
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Checkpoint {
+    repository: String,
+    after: u64,
+}
+
+fn checkpoint_error(path: &std::path::Path, problem: impl fmt::Display) -> Error {
+    Error::Checkpoint(format!("{}: {problem}", path.display()))
+}
+
+async fn load_checkpoint(options: &BackfillOptions) -> Result<Option<u64>, Error> {
+    let Some(path) = &options.checkpoint else {
+        return Ok(None);
+    };
+    let content = match tokio::fs::read(path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(checkpoint_error(path, e)),
+    };
+    let checkpoint: Checkpoint =
+        serde_json::from_slice(&content).map_err(|e| checkpoint_error(path, e))?;
+    if checkpoint.repository != options.repository {
+        return Err(checkpoint_error(
+            path,
+            format!("belongs to {}", checkpoint.repository),
+        ));
+    }
+    Ok(Some(checkpoint.after))
+}
+
+async fn save_checkpoint(options: &BackfillOptions, after: u64) -> Result<(), Error> {
+    let Some(path) = &options.checkpoint else {
+        return Ok(());
+    };
+    let checkpoint = Checkpoint {
+        repository: options.repository.clone(),
+        after,
+    };
+    tokio::fs::write(path, serde_json::to_vec_pretty(&checkpoint)?)
+        .await
+        .map_err(|e| checkpoint_error(path, e))
+}
+
+/// A pull request and what it did to the issue of its title
+#[derive(Debug, Serialize)]
+pub struct Backfilled {
+    pub number: u64,
+    pub url: String,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BackfillReport {
+    /// Pull requests with an issue key in the title
+    pub pull_requests: Vec<Backfilled>,
+    /// Pull requests looked at, with or without issue key
+    pub checked: usize,
+    pub checkpoint: Option<u64>,
+    pub errors: Vec<String>,
+}
+
+impl BackfillReport {
+    pub fn passed(&self) -> bool {
+        self.errors.is_empty()
+            && self
+                .pull_requests
+                .iter()
+                .all(|backfilled| backfilled.outcome.error().is_none())
+    }
+}
+
+impl fmt::Display for BackfillReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for Backfilled {
+            number,
+            url,
+            outcome,
+        } in &self.pull_requests
+        {
+            for issue in &outcome.issues {
+                let issue_key = &issue.issue_key;
+                match &issue.result {
+                    IssueResult::Updated if outcome.dry_run => {
+                        writeln!(f, "WOULD LINK #{number} {issue_key} {url}")?
+                    }
+                    IssueResult::Updated => writeln!(f, "LINKED #{number} {issue_key} {url}")?,
+                    IssueResult::Failed { error } => {
+                        writeln!(f, "FAIL #{number} {issue_key} {url}: {error}")?
+                    }
+                    result => writeln!(f, "SKIP #{number} {issue_key} {url}: {result:?}")?,
+                }
+            }
+        }
+        for error in &self.errors {
+            writeln!(f, "FAIL {error}")?;
+        }
+        write!(
+            f,
+            "{} pull requests checked, {} with issue key",
+            self.checked,
+            self.pull_requests.len()
+        )?;
+        match self.checkpoint {
+            Some(after) => writeln!(f, ", done up to #{after}"),
+            None => writeln!(f),
+        }
+    }
+}
+
+/// Pages through the pull requests of the repository, oldest first, and handles each like its
+/// latest event, `opened` for open and `closed` for merged or closed pull requests
+pub async fn backfill<T: IssueTracker>(
+    jira: &JiraRegistry<T>,
+    github: &GitHubClient,
+    routing: &RoutingConfig,
+    options: &BackfillOptions,
+) -> Result<BackfillReport, Error> {
+    // fail before paging if the repository is not routed
+    routing.route(&options.repository)?;
+
+    let mut report = BackfillReport {
+        checkpoint: load_checkpoint(options).await?,
+        ..BackfillReport::default()
+    };
+    let routing = Arc::new(routing.clone());
+    let permits = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    // a failed pull request keeps the checkpoint in front of it, so the next run retries it
+    let mut failed = false;
+
+    let mut next = None;
+    'pages: loop {
+        let page = match github
+            .pull_requests(&options.repository, &options.state, next)
+            .await
+        {
+            Ok(page) => page,
+            Err(error) => {
+                report
+                    .errors
+                    .push(format!("{}: {error}", options.repository));
+                break;
+            }
+        };
+
+        let mut tasks = JoinSet::new();
+        let mut done = false;
+        for pull_request in page.items {
+            if options
+                .until
+                .is_some_and(|until| pull_request.created_at >= until)
+            {
+                done = true;
+                break;
+            }
+            if report
+                .checkpoint
+                .is_some_and(|after| pull_request.number <= after)
+                || options
+                    .since
+                    .is_some_and(|since| pull_request.created_at < since)
+            {
+                continue;
+            }
+
+            let action = if pull_request.state == "open" {
+                "opened"
+            } else {
+                "closed"
+            };
+            let payload = PullRequestPayload {
+                action: action.to_string(),
+                pull_request,
+                changes: None,
+                repository: Repository {
+                    full_name: options.repository.clone(),
+                },
+            };
+            let jira = jira.clone();
+            let routing = routing.clone();
+            let permits = permits.clone();
+            let dry_run = options.dry_run;
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await.expect("Never closed");
+                let number = payload.pull_request.number;
+                let url = payload.pull_request.html_url.clone();
+                let outcome = handle_pull_request_event(payload, jira, &routing, dry_run).await;
+                (number, url, outcome)
+            });
+        }
+
+        let mut results = Vec::with_capacity(tasks.len());
+        while let Some(result) = tasks.join_next().await {
+            match result {
+                Ok(result) => results.push(result),
+                Err(error) => {
+                    report
+                        .errors
+                        .push(format!("{}: {error}", options.repository));
+                    failed = true;
+                }
+            }
+        }
+        results.sort_by_key(|(number, ..)| *number);
+
+        for (number, url, outcome) in results {
+            report.checked += 1;
+            let outcome = match outcome {
+                Ok(outcome) => outcome,
+                Err(error) => {
+                    report.errors.push(format!("#{number}: {error}"));
+                    failed = true;
+                    continue;
+                }
+            };
+            failed |= outcome.error().is_some();
+            if !failed {
+                report.checkpoint = Some(number);
+            }
+            if !outcome.issue_keys.is_empty() {
+                report.pull_requests.push(Backfilled {
+                    number,
+                    url,
+                    outcome,
+                });
+            }
+        }
+
+        // a dry run must not let the real run skip anything
+        if !options.dry_run {
+            if let Some(after) = report.checkpoint {
+                save_checkpoint(options, after).await?;
+            }
+        }
+
+        match page.next {
+            Some(url) if !done => next = Some(url),
+            _ => break 'pages,
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jira::fake::FakeIssueTracker;
+    use crate::jira::PrStatus;
+    use crate::routing::DEFAULT_SITE;
+    use crate::types::Secret;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const CHECKLIST: &str =
+        "# Development Process\n-! Task 1: Create Pull Request\n# Pull Requests";
+
+    fn pull_request(number: u64, title: &str, merged: bool) -> serde_json::Value {
+        serde_json::json!({
+            "title": title,
+            "html_url": format!("https://github.com/example-org/widgets/pull/{number}"),
+            "number": number,
+            "state": if merged { "closed" } else { "open" },
+            "created_at": format!("2026-10-0{number}T10:00:00Z"),
+            "updated_at": format!("2026-10-0{number}T12:00:00Z"),
+            "closed_at": merged.then(|| format!("2026-10-0{number}T12:00:00Z")),
+            "merged_at": merged.then(|| format!("2026-10-0{number}T12:00:00Z"))
+        })
+    }
+
+    async fn github(server: &MockServer) -> GitHubClient {
+        Mock::given(method("GET"))
+            .and(path("/repos/example-org/widgets/pulls"))
+            .and(query_param("state", "all"))
+            .and(query_param("direction", "asc"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                pull_request(1, "[ABC-1] Merged change", true),
+                pull_request(2, "Change without issue", false),
+                pull_request(3, "[ABC-3] Open change", false),
+            ])))
+            .mount(server)
+            .await;
+        GitHubClient::new(
+            reqwest::Url::parse(&format!("{}/", server.uri())).unwrap(),
+            &Secret::from("token".to_string()),
+        )
+        .unwrap()
+    }
+
+    fn jira(tracker: &FakeIssueTracker) -> JiraRegistry<FakeIssueTracker> {
+        JiraRegistry::from_iter([(DEFAULT_SITE.to_string(), tracker.clone())])
+    }
+
+    fn options(checkpoint: Option<PathBuf>, dry_run: bool) -> BackfillOptions {
+        BackfillOptions {
+            repository: "example-org/widgets".to_string(),
+            checkpoint,
+            dry_run,
+            ..BackfillOptions::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn dry_run_changes_nothing() {
+        let server = MockServer::start().await;
+        let github = github(&server).await;
+        let tracker = FakeIssueTracker::default()
+            .with_issue("ABC-1", Some(CHECKLIST))
+            .with_issue("ABC-3", Some(CHECKLIST));
+        let checkpoint = std::env::temp_dir().join(format!(
+            "github-webhook-backfill-dry-run-{}.json",
+            std::process::id()
+        ));
+
+        let report = backfill(
+            &jira(&tracker),
+            &github,
+            &RoutingConfig::default(),
+            &options(Some(checkpoint.clone()), true),
+        )
+        .await
+        .unwrap();
+
+        assert!(report.passed());
+        assert_eq!(report.checked, 3);
+        assert_eq!(report.pull_requests.len(), 2);
+        assert!(report.to_string().contains("WOULD LINK #1 ABC-1"));
+        assert!(tracker.updates().is_empty());
+        assert!(!checkpoint.exists());
+    }
+
+    #[tokio::test]
+    async fn resumes_after_checkpoint() {
+        let server = MockServer::start().await;
+        let github = github(&server).await;
+        // ABC-3 is missing, so #3 fails and stays in front of the checkpoint
+        let tracker = FakeIssueTracker::default().with_issue("ABC-1", Some(CHECKLIST));
+        let checkpoint = std::env::temp_dir().join(format!(
+            "github-webhook-backfill-resume-{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&checkpoint);
+        let options = options(Some(checkpoint.clone()), false);
+
+        let report = backfill(
+            &jira(&tracker),
+            &github,
+            &RoutingConfig::default(),
+            &options,
+        )
+        .await
+        .unwrap();
+
+        assert!(!report.passed());
+        assert_eq!(report.checkpoint, Some(2));
+        assert_eq!(
+            tracker.checklist("ABC-1").unwrap(),
+            format!("{CHECKLIST}\n+ https://github.com/example-org/widgets/pull/1")
+        );
+        assert_eq!(
+            tracker.pull_requests("ABC-1").pull_requests[0].status,
+            PrStatus::Merged
+        );
+
+        let tracker = tracker.with_issue("ABC-3", Some(CHECKLIST));
+        let report = backfill(
+            &jira(&tracker),
+            &github,
+            &RoutingConfig::default(),
+            &options,
+        )
+        .await
+        .unwrap();
+
+        assert!(report.passed());
+        assert_eq!(report.checked, 1);
+        assert_eq!(report.pull_requests[0].number, 3);
+        assert_eq!(load_checkpoint(&options).await.unwrap(), Some(3));
+        std::fs::remove_file(&checkpoint).unwrap();
+    }
+}
@@ -0,0 +1,41 @@
+use github_webhook::github::client::GitHubClient;
+use github_webhook::reconcile::{self, ReconcileReport};
+use github_webhook::{secrets, state};
+use lambda_http::lambda_runtime::{self, service_fn, LambdaEvent};
+use lambda_http::tracing;
+use std::sync::Arc;
+
+/// Corrects checklist entries of pull requests closed while their events were missed, invoked by
+/// an EventBridge schedule
+#[tokio::main]
+async fn main() -> Result<(), lambda_runtime::Error> {
+    tracing::init_default_subscriber();
+
+    let secrets = secrets::SecretStore::from_env().await?;
+    let github = Arc::new(GitHubClient::from_env(&secrets).await?);
+    let jql = Arc::new(reconcile::jql_from_env());
+
+    let state = match state::SharedState::load(secrets).await {
+        Ok(state) => Arc::new(state),
+        Err(error) => {
+            tracing::error!("{error}");
+            return Err(error.into());
+        }
+    };
+
+    lambda_runtime::run(service_fn(move |_: LambdaEvent<serde_json::Value>| {
+        let state = state.clone();
+        let github = github.clone();
+        let jql = jql.clone();
+
+        async move {
+            let state = state.current().await;
+            let report =
+                reconcile::reconcile(&state.jira, &github, &state.routing, &jql, state.dry_run)
+                    .await;
+            tracing::info!("{report}");
+            Ok::<ReconcileReport, lambda_runtime::Error>(report)
+        }
+    }))
+    .await
+}
@@ -0,0 +1,33 @@
+use github_webhook::github::client::GitHubClient;
+use github_webhook::redelivery::{self, RedeliveryReport};
+use github_webhook::secrets;
+use lambda_http::lambda_runtime::{self, service_fn, LambdaEvent};
+use lambda_http::tracing;
+use std::sync::Arc;
+
+/// Redelivers failed deliveries, invoked by an EventBridge schedule
+#[tokio::main]
+async fn main() -> Result<(), lambda_runtime::Error> {
+    tracing::init_default_subscriber();
+
+    let secrets = secrets::SecretStore::from_env().await?;
+    let github = Arc::new(GitHubClient::from_env(&secrets).await?);
+    let hooks = Arc::new(redelivery::hooks_from_env()?);
+    let cursors: Arc<dyn redelivery::CursorStore> =
+        redelivery::cursor_store_from_env().await?.into();
+
+    lambda_runtime::run(service_fn(move |_: LambdaEvent<serde_json::Value>| {
+        let github = github.clone();
+        let hooks = hooks.clone();
+        let cursors = cursors.clone();
+
+        async move {
+            let report =
+                redelivery::redeliver_failed(&github, &hooks, cursors.as_ref(), chrono::Utc::now())
+                    .await;
+            tracing::info!("{report}");
+            Ok::<RedeliveryReport, lambda_runtime::Error>(report)
+        }
+    }))
+    .await
+}
@@ -0,0 +1,76 @@
+use github_webhook::archive::{self, DeliveryArchive};
+use github_webhook::deliveries::{self, DeliveryStore};
+use github_webhook::http_handler::{self, WebhookRequest};
+use github_webhook::server::{self, ServerConfig};
+use github_webhook::{cli, secrets, state};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tracing_subscriber::EnvFilter;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
+
+    let secrets = secrets::SecretStore::from_env().await?;
+
+    if let Some(code) = cli::run(&secrets, std::env::args().skip(1)).await {
+        std::process::exit(code);
+    }
+
+    let server_config = ServerConfig::from_env()?;
+    let tls = server_config.tls_acceptor()?;
+
+    let deliveries: Arc<dyn DeliveryStore> = deliveries::from_env().await?.into();
+    let archive: Option<Arc<dyn DeliveryArchive>> = archive::from_env().await?.map(Arc::from);
+
+    let state = match state::SharedState::load(secrets).await {
+        Ok(state) => Arc::new(state),
+        Err(error) => {
+            tracing::error!("{error}");
+            return Err(error.into());
+        }
+    };
+
+    let listener = TcpListener::bind(server_config.bind_address).await?;
+    tracing::info!(
+        address = %server_config.bind_address,
+        tls = tls.is_some(),
+        "Listening"
+    );
+
+    server::serve(
+        listener,
+        tls,
+        server_config.body_limit,
+        server_config.timeouts,
+        move |request| {
+            let state = state.clone();
+            let deliveries = deliveries.clone();
+            let archive = archive.clone();
+
+            async move {
+                let state = state.current().await;
+                let request = WebhookRequest::from(&request);
+                let result = http_handler::function_handler(
+                    state.jira.clone(),
+                    &state.webhook_secrets,
+                    deliveries.as_ref(),
+                    request,
+                    &state.routing,
+                    state.dry_run,
+                )
+                .await;
+                if let Some(archive) = &archive {
+                    archive::archive_failure(archive.as_ref(), request, &result).await;
+                }
+                http_handler::response(result, request.delivery_id())
+            }
+        },
+        server::shutdown_signal(),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use github_webhook::secrets;
+use github_webhook::simulate::{self, SimulateArgs};
+
+/// Signs a delivery and sends it to a webhook or handles it in-process, for local testing
+#[tokio::main]
+async fn main() {
+    if std::env::args().any(|arg| arg == "--help") {
+        print!("{}", simulate::USAGE);
+        return;
+    }
+
+    let args = match SimulateArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(error) => {
+            eprint!("{error}\n\n{}", simulate::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let result = match secrets::SecretStore::from_env().await {
+        Ok(secrets) => simulate::run(&secrets, args).await,
+        Err(error) => Err(error),
+    };
+    match result {
+        Ok((status, body)) => {
+            println!("{status}");
+            println!("{body}");
+            std::process::exit(if status.is_success() { 0 } else { 1 });
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use aws_lambda_events::sqs::{SqsBatchResponse, SqsEvent};
+use github_webhook::archive::{self, DeliveryArchive};
+use github_webhook::deliveries::{self, DeliveryStore};
+use github_webhook::{lambda, queue, secrets, state};
+use lambda_http::lambda_runtime::{self, service_fn, LambdaEvent};
+use lambda_http::tracing;
+use std::sync::Arc;
+
+/// Handles the deliveries the webhook enqueued in asynchronous mode
+#[tokio::main]
+async fn main() -> Result<(), lambda_runtime::Error> {
+    tracing::init_default_subscriber();
+
+    let secrets = secrets::SecretStore::from_env().await?;
+    let deliveries: Arc<dyn DeliveryStore> = deliveries::from_env().await?.into();
+    let archive: Option<Arc<dyn DeliveryArchive>> = archive::from_env().await?.map(Arc::from);
+    let max_receives = queue::max_receives_from_env()?;
+
+    let state = match state::SharedState::load(secrets).await {
+        Ok(state) => Arc::new(state),
+        Err(error) => {
+            tracing::error!("{error}");
+            return Err(error.into());
+        }
+    };
+
+    lambda_runtime::run(service_fn(move |event: LambdaEvent<SqsEvent>| {
+        let state = state.clone();
+        let deliveries = deliveries.clone();
+        let archive = archive.clone();
+
+        async move {
+            let state = state.current().await;
+            let failed = queue::process_batch(
+                state.jira.clone(),
+                &state.webhook_secrets,
+                deliveries.as_ref(),
+                archive.as_deref(),
+                &state.routing,
+                state.dry_run,
+                lambda::sqs_messages(event.payload, max_receives),
+            )
+            .await;
+            Ok::<SqsBatchResponse, lambda_runtime::Error>(lambda::sqs_batch_response(failed))
+        }
+    }))
+    .await
+}
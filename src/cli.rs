@@ -0,0 +1,363 @@
+//! Commands shared by the binaries, e.g. `github-webhook doctor`
+
+use crate::archive::{self, ReplayFilter};
+use crate::backfill::{self, BackfillOptions};
+use crate::deliveries::{self, DeliveryStore, MemoryDeliveryStore};
+use crate::doctor;
+use crate::error::Error;
+use crate::github::client::GitHubClient;
+use crate::reconcile;
+use crate::redelivery;
+use crate::secrets::SecretStore;
+use crate::state::State;
+use chrono::{DateTime, NaiveDate, Utc};
+
+const USAGE: &str = "\
+Usage:
+  doctor [ISSUE_KEY]
+  replay [--since TIME] [--until TIME] [--repository OWNER/NAME] [--error-kind CODE] [--dry-run]
+         [--skip-signature]
+  redeliver
+  reconcile [--jql JQL] [--dry-run]
+  backfill OWNER/NAME [--state open|closed|all] [--since TIME] [--until TIME] [--concurrency N]
+           [--checkpoint FILE] [--apply]
+
+TIME is a date like 2026-10-01 or an RFC 3339 timestamp, an --until date includes that day.
+backfill only reports what it would change unless --apply is given.
+";
+
+/// Runs the command given by `args`, returns the exit code or `None` if there is no command and
+/// the binary handles deliveries
+pub async fn run(secrets: &SecretStore, mut args: impl Iterator<Item = String>) -> Option<i32> {
+    match args.next().as_deref() {
+        Some("doctor") => {
+            let report = doctor::run(secrets, args.next().as_deref()).await;
+            print!("{report}");
+            Some(if report.passed() { 0 } else { 1 })
+        }
+        Some("replay") => Some(match ReplayArgs::parse(args) {
+            Ok(replay_args) => replay(secrets, replay_args).await,
+            Err(error) => {
+                eprint!("{error}\n\n{USAGE}");
+                2
+            }
+        }),
+        Some("redeliver") => Some(redeliver(secrets).await),
+        Some("reconcile") => Some(match ReconcileArgs::parse(args) {
+            Ok(reconcile_args) => reconcile(secrets, reconcile_args).await,
+            Err(error) => {
+                eprint!("{error}\n\n{USAGE}");
+                2
+            }
+        }),
+        Some("backfill") => Some(match parse_backfill(args) {
+            Ok(options) => run_backfill(secrets, options).await,
+            Err(error) => {
+                eprint!("{error}\n\n{USAGE}");
+                2
+            }
+        }),
+        Some("help" | "--help") => {
+            print!("{USAGE}");
+            Some(0)
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default)]
+struct ReplayArgs {
+    filter: ReplayFilter,
+    dry_run: bool,
+    skip_signature: bool,
+}
+
+impl ReplayArgs {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut replay_args = Self::default();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+            match arg.as_str() {
+                "--since" => replay_args.filter.since = Some(parse_time(&value()?, false)?),
+                "--until" => replay_args.filter.until = Some(parse_time(&value()?, true)?),
+                "--repository" => replay_args.filter.repository = Some(value()?),
+                "--error-kind" => replay_args.filter.error_code = Some(value()?),
+                "--dry-run" => replay_args.dry_run = true,
+                "--skip-signature" => replay_args.skip_signature = true,
+                _ => return Err(format!("Unknown argument {arg}")),
+            }
+        }
+        Ok(replay_args)
+    }
+}
+
+#[derive(Debug, Default)]
+struct ReconcileArgs {
+    jql: Option<String>,
+    dry_run: bool,
+}
+
+impl ReconcileArgs {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut reconcile_args = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--jql" => {
+                    reconcile_args.jql = Some(args.next().ok_or("--jql needs a value")?);
+                }
+                "--dry-run" => reconcile_args.dry_run = true,
+                _ => return Err(format!("Unknown argument {arg}")),
+            }
+        }
+        Ok(reconcile_args)
+    }
+}
+
+fn parse_backfill(mut args: impl Iterator<Item = String>) -> Result<BackfillOptions, String> {
+    let mut options = BackfillOptions::default();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--state" => {
+                options.state = value()?;
+                if !["open", "closed", "all"].contains(&options.state.as_str()) {
+                    return Err(format!("Invalid state {}", options.state));
+                }
+            }
+            "--since" => options.since = Some(parse_time(&value()?, false)?),
+            "--until" => options.until = Some(parse_time(&value()?, true)?),
+            "--concurrency" => {
+                options.concurrency = value()?
+                    .parse()
+                    .ok()
+                    .filter(|concurrency| *concurrency > 0)
+                    .ok_or("--concurrency needs a positive number")?;
+            }
+            "--checkpoint" => options.checkpoint = Some(value()?.into()),
+            "--apply" => options.dry_run = false,
+            _ if options.repository.is_empty() && arg.contains('/') => options.repository = arg,
+            _ => return Err(format!("Unknown argument {arg}")),
+        }
+    }
+    if options.repository.is_empty() {
+        return Err("backfill needs a repository OWNER/NAME".to_string());
+    }
+    Ok(options)
+}
+
+/// Midnight UTC for dates, the following midnight for the end of a range
+fn parse_time(value: &str, end: bool) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let date = if end {
+            date.succ_opt().unwrap_or(date)
+        } else {
+            date
+        };
+        return Ok(date
+            .and_hms_opt(0, 0, 0)
+            .expect("Midnight exists")
+            .and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.to_utc())
+        .map_err(|_| format!("Invalid time {value}"))
+}
+
+async fn replay(secrets: &SecretStore, args: ReplayArgs) -> i32 {
+    let result = async {
+        let Some(archive) = archive::from_env().await? else {
+            return Err(Error::EnvVarNotSet {
+                env_var_name: "ARCHIVE_BUCKET or ARCHIVE_DIR".to_string(),
+            });
+        };
+        let state = State::load(secrets).await?;
+        let dry_run = state.dry_run || args.dry_run;
+        // a dry run must not mark the deliveries as handled for the real replay
+        let deliveries: Box<dyn DeliveryStore> = if dry_run {
+            Box::new(MemoryDeliveryStore::default())
+        } else {
+            deliveries::from_env().await?
+        };
+
+        if args.skip_signature {
+            tracing::warn!("Replaying without verifying signatures");
+        }
+        archive::replay(
+            archive.as_ref(),
+            &args.filter,
+            state.jira,
+            &state.webhook_secrets,
+            deliveries.as_ref(),
+            &state.routing,
+            dry_run,
+            args.skip_signature,
+        )
+        .await
+    }
+    .await;
+
+    exit_code(result.map(|report| {
+        print!("{report}");
+        report.passed()
+    }))
+}
+
+async fn redeliver(secrets: &SecretStore) -> i32 {
+    let result = async {
+        let github = GitHubClient::from_env(secrets).await?;
+        let hooks = redelivery::hooks_from_env()?;
+        let cursors = redelivery::cursor_store_from_env().await?;
+        Ok(redelivery::redeliver_failed(&github, &hooks, cursors.as_ref(), Utc::now()).await)
+    }
+    .await;
+
+    exit_code(result.map(|report| {
+        print!("{report}");
+        report.passed()
+    }))
+}
+
+async fn reconcile(secrets: &SecretStore, args: ReconcileArgs) -> i32 {
+    let result = async {
+        let github = GitHubClient::from_env(secrets).await?;
+        let state = State::load(secrets).await?;
+        let jql = args.jql.unwrap_or_else(reconcile::jql_from_env);
+        Ok(reconcile::reconcile(
+            &state.jira,
+            &github,
+            &state.routing,
+            &jql,
+            state.dry_run || args.dry_run,
+        )
+        .await)
+    }
+    .await;
+
+    exit_code(result.map(|report| {
+        print!("{report}");
+        report.passed()
+    }))
+}
+
+async fn run_backfill(secrets: &SecretStore, mut options: BackfillOptions) -> i32 {
+    let result = async {
+        let github = GitHubClient::from_env(secrets).await?;
+        let state = State::load(secrets).await?;
+        options.dry_run |= state.dry_run;
+        backfill::backfill(&state.jira, &github, &state.routing, &options).await
+    }
+    .await;
+
+    exit_code(result.map(|report| {
+        print!("{report}");
+        report.passed()
+    }))
+}
+
+fn exit_code(result: Result<bool, Error>) -> i32 {
+    match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(error) => {
+            eprintln!("{error}");
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<ReplayArgs, String> {
+        ReplayArgs::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_replay_filters() {
+        let args = parse(&[
+            "--since",
+            "2026-10-01",
+            "--until",
+            "2026-10-02",
+            "--repository",
+            "example-org/widgets",
+            "--error-kind",
+            "jira_unavailable",
+            "--skip-signature",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            args.filter.since.unwrap().to_rfc3339(),
+            "2026-10-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            args.filter.until.unwrap().to_rfc3339(),
+            "2026-10-03T00:00:00+00:00"
+        );
+        assert_eq!(
+            args.filter.repository.as_deref(),
+            Some("example-org/widgets")
+        );
+        assert_eq!(args.filter.error_code.as_deref(), Some("jira_unavailable"));
+        assert!(args.skip_signature);
+        assert!(!args.dry_run);
+    }
+
+    #[test]
+    fn signature_is_verified_unless_asked_not_to() {
+        let args = parse(&["--since", "2026-10-01T12:00:00+02:00"]).unwrap();
+
+        assert_eq!(
+            args.filter.since.unwrap().to_rfc3339(),
+            "2026-10-01T10:00:00+00:00"
+        );
+        assert!(!args.skip_signature);
+        parse(&["--since"]).expect_err("Expected missing value");
+        parse(&["--force"]).expect_err("Expected unknown argument");
+    }
+
+    #[test]
+    fn parses_reconcile_arguments() {
+        let args = ReconcileArgs::parse(
+            ["--jql", "project = ABC", "--dry-run"]
+                .iter()
+                .map(|arg| arg.to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(args.jql.as_deref(), Some("project = ABC"));
+        assert!(args.dry_run);
+        ReconcileArgs::parse(["--jql".to_string()].into_iter())
+            .expect_err("Expected missing value");
+    }
+
+    #[test]
+    fn backfill_is_a_dry_run_unless_applied() {
+        let backfill = |args: &[&str]| parse_backfill(args.iter().map(|arg| arg.to_string()));
+
+        let options = backfill(&["example-org/widgets", "--state", "closed"]).unwrap();
+        assert_eq!(options.repository, "example-org/widgets");
+        assert_eq!(options.state, "closed");
+        assert_eq!(options.concurrency, backfill::DEFAULT_CONCURRENCY);
+        assert!(options.dry_run);
+
+        let options = backfill(&[
+            "example-org/widgets",
+            "--concurrency",
+            "8",
+            "--checkpoint",
+            "widgets.json",
+            "--apply",
+        ])
+        .unwrap();
+        assert_eq!(options.concurrency, 8);
+        assert_eq!(options.checkpoint, Some("widgets.json".into()));
+        assert!(!options.dry_run);
+
+        backfill(&["--apply"]).expect_err("Expected missing repository");
+        backfill(&["example-org/widgets", "--state", "merged"]).expect_err("Expected bad state");
+        backfill(&["example-org/widgets", "--concurrency", "0"]).expect_err("Expected bad number");
+    }
+}
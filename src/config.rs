@@ -1,99 +1,380 @@
 use crate::error::Error;
-use crate::jira::models::JiraConfig;
-use crate::types::WebhookSecret;
-use aws_sdk_kms::primitives::Blob;
+use crate::jira::models::{JiraAuth, JiraConfig, JiraFlavor};
+use crate::jira::oauth::{OAuthCredentials, DEFAULT_TOKEN_URL};
+use crate::jira::JiraClient;
+use crate::routing::{RoutingConfig, SiteConfig, DEFAULT_SITE};
+use crate::secrets::SecretStore;
+use crate::types::{Secret, WebhookSecret, WebhookSecretMap, WebhookSecrets};
+use std::collections::BTreeMap;
 
 pub struct Config {
     pub dry_run: bool,
-    pub webhook_secret: WebhookSecret,
-    pub jira_config: JiraConfig,
+    pub webhook_secrets: WebhookSecretMap,
+    /// Jira sites by name, the site configured via environment is named `default`
+    pub jira_sites: BTreeMap<String, JiraConfig>,
+    pub routing: RoutingConfig,
 }
 
 const DRY_RUN: &str = "DRY_RUN";
+const CONFIG_FILE: &str = "CONFIG_FILE";
+const CONFIG_BASE64: &str = "CONFIG_BASE64";
 const WEBHOOK_SECRET: &str = "WEBHOOK_SECRET";
-const WEBHOOK_SECRET_KMS: &str = "WEBHOOK_SECRET_KMS";
-const JIRA_URL: &str = "JIRA_URL";
-const JIRA_EMAIL: &str = "JIRA_EMAIL";
-const JIRA_TOKEN: &str = "JIRA_TOKEN";
-const JIRA_TOKEN_KMS: &str = "JIRA_TOKEN_KMS";
+const JIRA_FLAVOR: &str = "JIRA_FLAVOR";
+// documented names first, followed by their aliases
+const JIRA_URL: &[&str] = &["JIRA_BASE_URL", "JIRA_URL"];
+const JIRA_EMAIL: &[&str] = &["JIRA_USER_EMAIL", "JIRA_EMAIL"];
+const JIRA_TOKEN: &[&str] = &["JIRA_API_TOKEN", "JIRA_TOKEN"];
+const JIRA_CLIENT_ID: &str = "JIRA_CLIENT_ID";
+const JIRA_CLIENT_SECRET: &str = "JIRA_CLIENT_SECRET";
+const JIRA_TOKEN_URL: &str = "JIRA_TOKEN_URL";
+
+/// Collects config problems so that all of them are reported at once
+#[derive(Default)]
+struct Problems(Vec<Error>);
+
+impl Problems {
+    fn check<T>(&mut self, result: Result<T, Error>) -> Option<T> {
+        result.map_err(|error| self.0.push(error)).ok()
+    }
+
+    fn push(&mut self, error: Error) {
+        self.0.push(error);
+    }
+}
 
 impl Config {
-    pub async fn from_env(aws_kms: &aws_sdk_kms::Client) -> Result<Self, Error> {
-        use std::env::{var, VarError};
+    pub async fn from_env(secrets: &SecretStore) -> Result<Self, Error> {
+        use std::env::VarError;
 
-        let dry_run = match var(DRY_RUN) {
+        let mut problems = Problems::default();
+
+        let dry_run = match secrets.var(DRY_RUN) {
             Err(VarError::NotPresent) => false,
             Ok(value) => !value.is_empty(),
             Err(VarError::NotUnicode(var)) => !var.is_empty(),
         };
 
-        let webhook_secret = get_encrypted_var(WEBHOOK_SECRET, WEBHOOK_SECRET_KMS, &aws_kms)
-            .await
-            .and_then(WebhookSecret::new)?;
-
-        let jira_email = var(JIRA_EMAIL).map_err(|_| Error::EnvVarNotSet {
-            env_var_name: JIRA_EMAIL,
-        })?;
-        let jira_token = get_encrypted_var(JIRA_TOKEN, JIRA_TOKEN_KMS, &aws_kms).await?;
-        let jira_url = var(JIRA_URL)
-            .map_err(|_| Error::EnvVarNotSet {
-                env_var_name: JIRA_URL,
-            })
-            .and_then(|url| {
-                reqwest::Url::parse(&url).map_err(|_| Error::EnvVarBadValue {
-                    env_var_name: JIRA_URL,
-                })
-            })
-            .map(|mut url| {
-                // Url must end in a slash so that it can be used as a base for other urls
-                if !url.path().ends_with("/") {
-                    url.set_path(&format!("{}/", url.path()));
-                }
-                url
-            })?;
+        let routing = problems
+            .check(load_routing_config(secrets))
+            .unwrap_or_default();
+
+        // the default secret is optional if the config file maps secrets by origin
+        let default_secrets =
+            if secrets.is_set(WEBHOOK_SECRET) || routing.webhook_secrets.is_empty() {
+                problems.check(load_webhook_secrets(WEBHOOK_SECRET, secrets).await)
+            } else {
+                None
+            };
+        let mut webhook_secrets = WebhookSecretMap::new(default_secrets);
+        for (origin, secret_var) in &routing.webhook_secrets {
+            if let Some(origin_secrets) =
+                problems.check(load_webhook_secrets(secret_var, secrets).await)
+            {
+                webhook_secrets.insert(origin, origin_secrets);
+            }
+        }
+
+        let mut jira_sites = BTreeMap::new();
+        // the site configured via environment is optional if the config file declares sites
+        let has_default_site = env_var(secrets, JIRA_URL).is_some() || routing.sites.is_empty();
+        if has_default_site {
+            if let Some(jira_config) = jira_config_from_env(secrets, &mut problems).await {
+                jira_sites.insert(DEFAULT_SITE.to_string(), jira_config);
+            }
+        }
+        for (name, site) in &routing.sites {
+            if let Some(jira_config) =
+                jira_config_from_site(name, site, secrets, &mut problems).await
+            {
+                jira_sites.insert(name.clone(), jira_config);
+            }
+        }
+        for jira_config in jira_sites.values() {
+            problems.check(JiraClient::check_credentials(jira_config));
+        }
+
+        for site in routing.referenced_sites() {
+            let declared =
+                routing.sites.contains_key(site) || (site == DEFAULT_SITE && has_default_site);
+            if !declared {
+                problems.push(Error::InvalidConfigFile(format!(
+                    "unknown Jira site `{site}`"
+                )));
+            }
+        }
+
+        if !problems.0.is_empty() {
+            return Err(Error::InvalidConfig(problems.0));
+        }
 
         Ok(Config {
-            jira_config: JiraConfig {
-                email: jira_email,
-                api_token: jira_token,
-                base_url: jira_url,
-            },
-            webhook_secret,
+            jira_sites,
+            webhook_secrets,
+            routing,
             dry_run,
         })
     }
 }
 
-async fn get_encrypted_var(
-    plain_text_name: &'static str,
-    encrypted_name: &'static str,
-    aws_kms: &aws_sdk_kms::Client,
-) -> Result<String, Error> {
-    if let Ok(encrypted_value) = std::env::var(encrypted_name) {
-        use base64::{engine::general_purpose::STANDARD as Base64, Engine as _};
-        let encrypted_bytes =
-            Base64
-                .decode(encrypted_value)
-                .map_err(|_| Error::EnvVarBadValue {
-                    env_var_name: encrypted_name,
-                })?;
-
-        let decrypted = aws_kms
-            .decrypt()
-            .ciphertext_blob(Blob::from(encrypted_bytes))
-            .send()
-            .await
-            .map_err(|e| Error::AwsKms(e.into()))?;
+/// Value of the first variable that is set
+fn env_var<'a>(secrets: &SecretStore, names: &[&'a str]) -> Option<(&'a str, String)> {
+    names
+        .iter()
+        .find_map(|name| secrets.var(name).ok().map(|value| (*name, value)))
+}
+
+/// Reports the documented name if none of the names is set
+fn required_env_var<'a>(
+    secrets: &SecretStore,
+    names: &[&'a str],
+) -> Result<(&'a str, String), Error> {
+    env_var(secrets, names).ok_or_else(|| Error::EnvVarNotSet {
+        env_var_name: names[0].to_string(),
+    })
+}
+
+/// The first secret that is set, reports the documented name if none is
+async fn secret_of(names: &[&str], secrets: &SecretStore) -> Result<Secret, Error> {
+    let name = names
+        .iter()
+        .find(|name| secrets.is_set(name))
+        .unwrap_or(&names[0]);
+    secrets.get(name).await
+}
+
+/// `<name>` followed by the deprecated `<name>_1`, `<name>_2`, ...
+async fn load_webhook_secrets(name: &str, secrets: &SecretStore) -> Result<WebhookSecrets, Error> {
+    let mut webhook_secrets = vec![secrets
+        .get(name)
+        .await
+        .and_then(WebhookSecret::from_secret)?];
+
+    for index in 1.. {
+        let deprecated = format!("{name}_{index}");
+        if !secrets.is_set(&deprecated) {
+            break;
+        }
+        webhook_secrets.push(
+            secrets
+                .get(&deprecated)
+                .await
+                .and_then(WebhookSecret::from_secret)?,
+        );
+    }
+
+    WebhookSecrets::new(webhook_secrets)
+}
+
+async fn jira_config_from_env(
+    secrets: &SecretStore,
+    problems: &mut Problems,
+) -> Option<JiraConfig> {
+    let jira_flavor = match secrets.var(JIRA_FLAVOR) {
+        Err(_) => JiraFlavor::default(),
+        Ok(flavor) => problems
+            .check(JiraFlavor::from_str(&flavor).ok_or(Error::EnvVarBadValue {
+                env_var_name: JIRA_FLAVOR.to_string(),
+            }))
+            .unwrap_or_default(),
+    };
+    let jira_auth = match (jira_flavor, secrets.var(JIRA_CLIENT_ID)) {
+        (JiraFlavor::Cloud, Ok(client_id)) => {
+            let client_secret = problems.check(secrets.get(JIRA_CLIENT_SECRET).await);
+            let token_url = problems.check(parse_url(
+                JIRA_TOKEN_URL,
+                &secrets
+                    .var(JIRA_TOKEN_URL)
+                    .unwrap_or(DEFAULT_TOKEN_URL.to_string()),
+            ));
+            client_secret
+                .zip(token_url)
+                .map(|(client_secret, token_url)| {
+                    JiraAuth::OAuth(OAuthCredentials {
+                        client_id,
+                        client_secret,
+                        token_url,
+                    })
+                })
+        }
+        (JiraFlavor::Cloud, Err(_)) => {
+            let email = problems.check(required_env_var(secrets, JIRA_EMAIL));
+            let api_token = problems.check(secret_of(JIRA_TOKEN, secrets).await);
+            email
+                .zip(api_token)
+                .map(|((_, email), api_token)| JiraAuth::Basic { email, api_token })
+        }
+        (JiraFlavor::DataCenter, _) => problems
+            .check(secret_of(JIRA_TOKEN, secrets).await)
+            .map(|token| JiraAuth::PersonalAccessToken { token }),
+    };
+    let jira_url = problems.check(
+        required_env_var(secrets, JIRA_URL)
+            .and_then(|(name, url)| parse_url(name, &url))
+            .map(with_trailing_slash),
+    );
+
+    Some(JiraConfig {
+        flavor: jira_flavor,
+        auth: jira_auth?,
+        base_url: jira_url?,
+    })
+}
 
-        let decrypted = decrypted.plaintext().ok_or(Error::Internal(
-            "decrypted value had no plain text".to_string(),
-        ))?;
+async fn jira_config_from_site(
+    name: &str,
+    site: &SiteConfig,
+    secrets: &SecretStore,
+    problems: &mut Problems,
+) -> Option<JiraConfig> {
+    let missing = |key: &str| Error::InvalidConfigFile(format!("site `{name}` is missing `{key}`"));
+    let invalid = |key: &str| Error::InvalidConfigFile(format!("site `{name}` has bad `{key}`"));
 
-        return Ok(String::from_utf8_lossy(decrypted.as_ref()).to_string());
+    let flavor = match &site.flavor {
+        None => JiraFlavor::default(),
+        Some(flavor) => problems
+            .check(JiraFlavor::from_str(flavor).ok_or_else(|| invalid("flavor")))
+            .unwrap_or_default(),
+    };
+    let token = async {
+        let token_var = site
+            .token_var
+            .as_deref()
+            .ok_or_else(|| missing("token_var"))?;
+        secrets.get(token_var).await
+    };
+    let auth = match (flavor, &site.client_id) {
+        (JiraFlavor::Cloud, Some(client_id)) => {
+            let client_secret = match site.client_secret_var.as_deref() {
+                None => Err(missing("client_secret_var")),
+                Some(secret_var) => secrets.get(secret_var).await,
+            };
+            let client_secret = problems.check(client_secret);
+            let token_url = problems.check(
+                reqwest::Url::parse(site.token_url.as_deref().unwrap_or(DEFAULT_TOKEN_URL))
+                    .map_err(|_| invalid("token_url")),
+            );
+            client_secret
+                .zip(token_url)
+                .map(|(client_secret, token_url)| {
+                    JiraAuth::OAuth(OAuthCredentials {
+                        client_id: client_id.clone(),
+                        client_secret,
+                        token_url,
+                    })
+                })
+        }
+        (JiraFlavor::Cloud, None) => {
+            let email = problems.check(site.email.clone().ok_or_else(|| missing("email")));
+            let api_token = problems.check(token.await);
+            email
+                .zip(api_token)
+                .map(|(email, api_token)| JiraAuth::Basic { email, api_token })
+        }
+        (JiraFlavor::DataCenter, _) => problems
+            .check(token.await)
+            .map(|token| JiraAuth::PersonalAccessToken { token }),
     };
+    let base_url = problems.check(
+        reqwest::Url::parse(&site.url)
+            .map(with_trailing_slash)
+            .map_err(|_| invalid("url")),
+    );
+
+    Some(JiraConfig {
+        flavor,
+        auth: auth?,
+        base_url: base_url?,
+    })
+}
+
+/// Url must end in a slash so that it can be used as a base for other urls
+fn with_trailing_slash(mut url: reqwest::Url) -> reqwest::Url {
+    if !url.path().ends_with("/") {
+        url.set_path(&format!("{}/", url.path()));
+    }
+    url
+}
+
+fn load_routing_config(secrets: &SecretStore) -> Result<RoutingConfig, Error> {
+    use base64::{engine::general_purpose::STANDARD as Base64, Engine as _};
+
+    let toml = if let Ok(path) = secrets.var(CONFIG_FILE) {
+        std::fs::read_to_string(&path)
+            .map_err(|e| Error::InvalidConfigFile(format!("failed to read {path}: {e}")))?
+    } else if let Ok(encoded) = secrets.var(CONFIG_BASE64) {
+        Base64
+            .decode(encoded)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or(Error::EnvVarBadValue {
+                env_var_name: CONFIG_BASE64.to_string(),
+            })?
+    } else {
+        return Ok(RoutingConfig::default());
+    };
+
+    RoutingConfig::from_toml(&toml)
+}
 
-    // fall back to plain text
-    std::env::var(plain_text_name).map_err(|_| Error::EnvVarNotSet {
-        env_var_name: plain_text_name,
+fn parse_url(env_var_name: &str, url: &str) -> Result<reqwest::Url, Error> {
+    reqwest::Url::parse(url).map_err(|_| Error::EnvVarBadValue {
+        env_var_name: env_var_name.to_string(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::Env;
+
+    #[tokio::test]
+    async fn reports_all_problems_at_once() {
+        let secrets = SecretStore::new(None).with_env(Env::from_iter([
+            ("JIRA_FLAVOR", "mainframe"),
+            ("JIRA_BASE_URL", "not a url"),
+            ("JIRA_TOKEN", "token"),
+        ]));
+
+        let error = Config::from_env(&secrets)
+            .await
+            .err()
+            .expect("Expected invalid config");
+
+        let Error::InvalidConfig(problems) = error else {
+            panic!("Expected problems, got {error:?}");
+        };
+        let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            problems,
+            [
+                "Environment variable not set: WEBHOOK_SECRET",
+                "Environment variable JIRA_FLAVOR has bad value",
+                "Environment variable not set: JIRA_USER_EMAIL",
+                "Environment variable JIRA_BASE_URL has bad value",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_token_not_allowed_in_header() {
+        let secrets = SecretStore::new(None).with_env(Env::from_iter([
+            ("WEBHOOK_SECRET", "secret"),
+            ("JIRA_FLAVOR", "datacenter"),
+            ("JIRA_BASE_URL", "https://jira.example.com/"),
+            ("JIRA_TOKEN", "token\n"),
+        ]));
+
+        let error = Config::from_env(&secrets)
+            .await
+            .err()
+            .expect("Expected invalid config");
+
+        let Error::InvalidConfig(problems) = error else {
+            panic!("Expected problems, got {error:?}");
+        };
+        assert!(matches!(
+            problems.as_slice(),
+            [Error::InvalidJiraCredentials(url)] if url == "https://jira.example.com/"
+        ));
+    }
+}
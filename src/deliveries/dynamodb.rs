@@ -0,0 +1,192 @@
+use super::{Claim, DeliveryStore, IN_PROGRESS_LEASE};
+use crate::error::Error;
+use crate::secrets::BoxFuture;
+use aws_sdk_dynamodb::operation::put_item::PutItemError;
+use aws_sdk_dynamodb::types::{AttributeValue, ReturnValuesOnConditionCheckFailure};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DYNAMODB_ENDPOINT_URL: &str = "DYNAMODB_ENDPOINT_URL";
+
+/// Deliveries in a table with the string partition key `delivery_id`, enable DynamoDB TTL on the
+/// number attribute `expires_at` to have old deliveries removed, deliveries being handled are
+/// marked with `in_progress`
+pub struct DynamoDbDeliveryStore {
+    client: aws_sdk_dynamodb::Client,
+    table: String,
+    ttl: Duration,
+}
+
+impl DynamoDbDeliveryStore {
+    pub fn new(client: aws_sdk_dynamodb::Client, table: String, ttl: Duration) -> Self {
+        Self { client, table, ttl }
+    }
+
+    /// The endpoint can be overridden with `DYNAMODB_ENDPOINT_URL`, e.g. for DynamoDB local
+    pub fn from_env(aws_config: &aws_config::SdkConfig, table: String, ttl: Duration) -> Self {
+        let mut config = aws_sdk_dynamodb::config::Builder::from(aws_config);
+        if let Ok(url) = std::env::var(DYNAMODB_ENDPOINT_URL) {
+            config = config.endpoint_url(url);
+        }
+        Self::new(
+            aws_sdk_dynamodb::Client::from_conf(config.build()),
+            table,
+            ttl,
+        )
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is after the epoch")
+        .as_secs()
+}
+
+impl DeliveryStore for DynamoDbDeliveryStore {
+    fn claim<'a>(&'a self, delivery_id: &'a str) -> BoxFuture<'a, Result<Claim, Error>> {
+        Box::pin(async move {
+            let now = unix_time();
+            let lease = self.ttl.min(IN_PROGRESS_LEASE);
+            // TTL deletes items lazily, so expired items may still be there
+            let result = self
+                .client
+                .put_item()
+                .table_name(&self.table)
+                .item("delivery_id", AttributeValue::S(delivery_id.to_string()))
+                .item(
+                    "expires_at",
+                    AttributeValue::N((now + lease.as_secs()).to_string()),
+                )
+                .item("in_progress", AttributeValue::Bool(true))
+                .condition_expression("attribute_not_exists(delivery_id) OR expires_at < :now")
+                .expression_attribute_values(":now", AttributeValue::N(now.to_string()))
+                .return_values_on_condition_check_failure(
+                    ReturnValuesOnConditionCheckFailure::AllOld,
+                )
+                .send()
+                .await;
+
+            match result {
+                Ok(_) => Ok(Claim::Claimed),
+                Err(error) => match error.into_service_error() {
+                    // items recorded before `in_progress` was introduced are done
+                    PutItemError::ConditionalCheckFailedException(failed) => {
+                        let in_progress = failed
+                            .item()
+                            .and_then(|item| item.get("in_progress"))
+                            .is_some_and(|value| value == &AttributeValue::Bool(true));
+                        Ok(if in_progress {
+                            Claim::InProgress
+                        } else {
+                            Claim::Done
+                        })
+                    }
+                    error => Err(Error::DynamoDb(Box::new(error.into()))),
+                },
+            }
+        })
+    }
+
+    fn complete<'a>(&'a self, delivery_id: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            self.client
+                .put_item()
+                .table_name(&self.table)
+                .item("delivery_id", AttributeValue::S(delivery_id.to_string()))
+                .item(
+                    "expires_at",
+                    AttributeValue::N((unix_time() + self.ttl.as_secs()).to_string()),
+                )
+                .send()
+                .await
+                .map_err(|e| Error::DynamoDb(Box::new(e.into())))?;
+            Ok(())
+        })
+    }
+
+    fn release<'a>(&'a self, delivery_id: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            self.client
+                .delete_item()
+                .table_name(&self.table)
+                .key("delivery_id", AttributeValue::S(delivery_id.to_string()))
+                .send()
+                .await
+                .map_err(|e| Error::DynamoDb(Box::new(e.into())))?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::test_sdk_config as sdk_config;
+    use wiremock::matchers::{body_partial_json, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn store(server: &MockServer) -> DynamoDbDeliveryStore {
+        let client = aws_sdk_dynamodb::Client::new(&sdk_config(server));
+        DynamoDbDeliveryStore::new(client, "deliveries".to_string(), Duration::from_secs(60))
+    }
+
+    #[tokio::test]
+    async fn claims_with_condition() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("x-amz-target", "DynamoDB_20120810.PutItem"))
+            .and(body_partial_json(serde_json::json!({
+                "TableName": "deliveries",
+                "Item": {
+                    "delivery_id": { "S": "delivery-1" },
+                    "in_progress": { "BOOL": true }
+                },
+                "ConditionExpression": "attribute_not_exists(delivery_id) OR expires_at < :now",
+                "ReturnValuesOnConditionCheckFailure": "ALL_OLD"
+            })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw("{}", "application/x-amz-json-1.0"),
+            )
+            .mount(&server)
+            .await;
+
+        assert_eq!(
+            store(&server).claim("delivery-1").await.unwrap(),
+            Claim::Claimed
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_condition_is_a_duplicate() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_raw(
+                r#"{"__type":"com.amazonaws.dynamodb.v20120810#ConditionalCheckFailedException","message":"The conditional request failed","Item":{"delivery_id":{"S":"delivery-1"},"expires_at":{"N":"1"}}}"#,
+                "application/x-amz-json-1.0",
+            ))
+            .mount(&server)
+            .await;
+
+        assert_eq!(
+            store(&server).claim("delivery-1").await.unwrap(),
+            Claim::Done
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_condition_on_claimed_item_is_in_progress() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_raw(
+                r#"{"__type":"com.amazonaws.dynamodb.v20120810#ConditionalCheckFailedException","message":"The conditional request failed","Item":{"delivery_id":{"S":"delivery-1"},"expires_at":{"N":"1"},"in_progress":{"BOOL":true}}}"#,
+                "application/x-amz-json-1.0",
+            ))
+            .mount(&server)
+            .await;
+
+        assert_eq!(
+            store(&server).claim("delivery-1").await.unwrap(),
+            Claim::InProgress
+        );
+    }
+}
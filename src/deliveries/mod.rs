@@ -0,0 +1,169 @@
+//! Dedupe of deliveries by their `X-GitHub-Delivery` ID so that redeliveries are not applied twice
+
+use crate::error::Error;
+use crate::secrets::BoxFuture;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "aws-dynamodb")]
+mod dynamodb;
+
+#[cfg(feature = "aws-dynamodb")]
+pub use dynamodb::DynamoDbDeliveryStore;
+
+const DELIVERY_TABLE: &str = "DELIVERY_TABLE";
+const DELIVERY_TTL: &str = "DELIVERY_TTL";
+
+/// GitHub allows redelivering deliveries of the past three days
+const DEFAULT_TTL: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+
+/// How long a claimed delivery is considered in progress, an attempt that died without releasing
+/// its claim blocks redeliveries this long, Lambda functions time out after 15 minutes at most
+const IN_PROGRESS_LEASE: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Claim {
+    /// The delivery is handled by the caller
+    Claimed,
+    /// Another attempt is still handling the delivery
+    InProgress,
+    /// The delivery was already handled
+    Done,
+}
+
+/// Remembers handled deliveries until their TTL elapsed
+pub trait DeliveryStore: Send + Sync {
+    /// Records the delivery as in progress unless it is in progress or done already
+    fn claim<'a>(&'a self, delivery_id: &'a str) -> BoxFuture<'a, Result<Claim, Error>>;
+
+    /// Records the claimed delivery as done
+    fn complete<'a>(&'a self, delivery_id: &'a str) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Forgets the delivery so that a redelivery is handled again
+    fn release<'a>(&'a self, delivery_id: &'a str) -> BoxFuture<'a, Result<(), Error>>;
+}
+
+struct Recorded {
+    done: bool,
+    expires_at: Instant,
+}
+
+/// Deliveries of this process only, enough for a single server or a warm Lambda instance
+pub struct MemoryDeliveryStore {
+    ttl: Duration,
+    recorded: Mutex<HashMap<String, Recorded>>,
+}
+
+impl MemoryDeliveryStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            recorded: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for MemoryDeliveryStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_TTL)
+    }
+}
+
+impl DeliveryStore for MemoryDeliveryStore {
+    fn claim<'a>(&'a self, delivery_id: &'a str) -> BoxFuture<'a, Result<Claim, Error>> {
+        let now = Instant::now();
+        let mut recorded = self.recorded.lock().unwrap();
+        recorded.retain(|_, delivery| delivery.expires_at > now);
+        let claim = match recorded.get(delivery_id) {
+            Some(delivery) if delivery.done => Claim::Done,
+            Some(_) => Claim::InProgress,
+            None => {
+                recorded.insert(
+                    delivery_id.to_string(),
+                    Recorded {
+                        done: false,
+                        expires_at: now + self.ttl.min(IN_PROGRESS_LEASE),
+                    },
+                );
+                Claim::Claimed
+            }
+        };
+        Box::pin(std::future::ready(Ok(claim)))
+    }
+
+    fn complete<'a>(&'a self, delivery_id: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        self.recorded.lock().unwrap().insert(
+            delivery_id.to_string(),
+            Recorded {
+                done: true,
+                expires_at: Instant::now() + self.ttl,
+            },
+        );
+        Box::pin(std::future::ready(Ok(())))
+    }
+
+    fn release<'a>(&'a self, delivery_id: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        self.recorded.lock().unwrap().remove(delivery_id);
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+/// DynamoDB table `DELIVERY_TABLE` if set, else in memory, deliveries are kept for `DELIVERY_TTL`
+/// seconds
+pub async fn from_env() -> Result<Box<dyn DeliveryStore>, Error> {
+    let ttl = match std::env::var(DELIVERY_TTL) {
+        Err(_) => DEFAULT_TTL,
+        Ok(seconds) => Duration::from_secs(seconds.parse().map_err(|_| Error::EnvVarBadValue {
+            env_var_name: DELIVERY_TTL.to_string(),
+        })?),
+    };
+
+    match std::env::var(DELIVERY_TABLE) {
+        Err(_) => Ok(Box::new(MemoryDeliveryStore::new(ttl))),
+        #[cfg(feature = "aws-dynamodb")]
+        Ok(table) => {
+            let aws_config =
+                aws_config::load_defaults(aws_config::BehaviorVersion::v2026_01_12()).await;
+            Ok(Box::new(DynamoDbDeliveryStore::from_env(
+                &aws_config,
+                table,
+                ttl,
+            )))
+        }
+        #[cfg(not(feature = "aws-dynamodb"))]
+        Ok(_) => Err(Error::EnvVarBadValue {
+            env_var_name: DELIVERY_TABLE.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn second_claim_is_in_progress_until_completed() {
+        let store = MemoryDeliveryStore::default();
+
+        assert_eq!(store.claim("delivery-1").await.unwrap(), Claim::Claimed);
+        assert_eq!(store.claim("delivery-1").await.unwrap(), Claim::InProgress);
+        assert_eq!(store.claim("delivery-2").await.unwrap(), Claim::Claimed);
+
+        store.complete("delivery-1").await.unwrap();
+        assert_eq!(store.claim("delivery-1").await.unwrap(), Claim::Done);
+    }
+
+    #[tokio::test]
+    async fn released_and_expired_deliveries_are_claimed_again() {
+        let store = MemoryDeliveryStore::default();
+        store.claim("delivery-1").await.unwrap();
+        store.release("delivery-1").await.unwrap();
+        assert_eq!(store.claim("delivery-1").await.unwrap(), Claim::Claimed);
+
+        let store = MemoryDeliveryStore::new(Duration::ZERO);
+        store.claim("delivery-1").await.unwrap();
+        store.complete("delivery-1").await.unwrap();
+        assert_eq!(store.claim("delivery-1").await.unwrap(), Claim::Claimed);
+    }
+}
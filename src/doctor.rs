@@ -0,0 +1,266 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::jira::JiraClient;
+use crate::routing::RoutingConfig;
+use crate::secrets::SecretStore;
+use std::fmt;
+
+pub enum Outcome {
+    Pass(String),
+    Fail(String),
+    Skip(String),
+}
+
+pub struct Check {
+    pub name: String,
+    pub outcome: Outcome,
+}
+
+/// Pass/fail report of the `doctor` command
+#[derive(Default)]
+pub struct Report(Vec<Check>);
+
+impl Report {
+    fn add(&mut self, name: impl Into<String>, outcome: Outcome) {
+        self.0.push(Check {
+            name: name.into(),
+            outcome,
+        });
+    }
+
+    pub fn passed(&self) -> bool {
+        !self
+            .0
+            .iter()
+            .any(|check| matches!(check.outcome, Outcome::Fail(_)))
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.0 {
+            let (status, detail) = match &check.outcome {
+                Outcome::Pass(detail) => ("PASS", detail),
+                Outcome::Fail(detail) => ("FAIL", detail),
+                Outcome::Skip(detail) => ("SKIP", detail),
+            };
+            writeln!(f, "{status} {}: {detail}", check.name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Loads the config including all secrets and checks every Jira site, editability of the checklist
+/// fields is checked on `issue_key` if given
+pub async fn run(secrets: &SecretStore, issue_key: Option<&str>) -> Report {
+    let mut report = Report::default();
+
+    let config = match Config::from_env(secrets).await {
+        Ok(config) => {
+            report.add(
+                "config",
+                Outcome::Pass("loaded and all secrets decrypted".to_string()),
+            );
+            config
+        }
+        Err(Error::InvalidConfig(problems)) => {
+            for problem in problems {
+                report.add("config", Outcome::Fail(problem.to_string()));
+            }
+            return report;
+        }
+        Err(error) => {
+            report.add("config", Outcome::Fail(error.to_string()));
+            return report;
+        }
+    };
+
+    let issue_site = issue_key.map(|issue_key| config.routing.site_for_project(issue_key));
+    for (name, jira_config) in &config.jira_sites {
+        let fields = checklist_fields(&config.routing, name);
+        let issue_key = issue_key.filter(|_| issue_site == Some(name.as_str()));
+        match JiraClient::new(jira_config.clone()) {
+            Ok(client) => check_site(&mut report, name, &client, &fields, issue_key).await,
+            Err(error) => report.add(
+                format!("{name} credentials"),
+                Outcome::Fail(error.to_string()),
+            ),
+        }
+    }
+
+    report
+}
+
+/// Checklist fields of all rules that may update issues on the site
+fn checklist_fields(routing: &RoutingConfig, site: &str) -> Vec<String> {
+    let mut fields: Vec<String> = routing
+        .rules
+        .iter()
+        .filter(|rule| rule.jira_site.as_deref().is_none_or(|name| name == site))
+        .map(|rule| rule.checklist_field.clone())
+        .collect();
+    fields.sort();
+    fields.dedup();
+    fields
+}
+
+async fn check_site(
+    report: &mut Report,
+    site: &str,
+    client: &JiraClient,
+    fields: &[String],
+    issue_key: Option<&str>,
+) {
+    match client.myself().await {
+        Ok(user) => report.add(
+            format!("{site} credentials"),
+            Outcome::Pass(format!("authenticated as {}", user.display_name)),
+        ),
+        Err(error) => {
+            report.add(
+                format!("{site} credentials"),
+                Outcome::Fail(error.to_string()),
+            );
+            return;
+        }
+    }
+
+    match client.fields().await {
+        Ok(known) => {
+            for field in fields {
+                let outcome = match known.iter().find(|known| &known.id == field) {
+                    Some(known) => Outcome::Pass(format!("exists as \"{}\"", known.name)),
+                    None => Outcome::Fail("does not exist".to_string()),
+                };
+                report.add(format!("{site} field {field}"), outcome);
+            }
+        }
+        Err(error) => report.add(format!("{site} fields"), Outcome::Fail(error.to_string())),
+    }
+
+    let Some(issue_key) = issue_key else {
+        report.add(
+            format!("{site} editable"),
+            Outcome::Skip(
+                "pass an issue key of this site to check the fields are editable".to_string(),
+            ),
+        );
+        return;
+    };
+
+    match client.editable_fields(issue_key).await {
+        Ok(editable) => {
+            for field in fields {
+                let outcome = if editable.contains(field) {
+                    Outcome::Pass(format!("editable on {issue_key}"))
+                } else {
+                    Outcome::Fail(format!("not editable on {issue_key}"))
+                };
+                report.add(format!("{site} field {field}"), outcome);
+            }
+        }
+        Err(error) => report.add(format!("{site} editable"), Outcome::Fail(error.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jira::models::{JiraAuth, JiraConfig, JiraFlavor};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn mock(server: &MockServer, url_path: &str, status: u16, body: serde_json::Value) {
+        Mock::given(method("GET"))
+            .and(path(url_path))
+            .respond_with(ResponseTemplate::new(status).set_body_json(body))
+            .mount(server)
+            .await;
+    }
+
+    fn client(server: &MockServer) -> JiraClient {
+        JiraClient::new(JiraConfig {
+            flavor: JiraFlavor::Cloud,
+            auth: JiraAuth::Basic {
+                email: "bot@example.com".to_string(),
+                api_token: "token".into(),
+            },
+            base_url: reqwest::Url::parse(&format!("{}/", server.uri())).unwrap(),
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn reports_each_check() {
+        let server = MockServer::start().await;
+        mock(
+            &server,
+            "/rest/api/3/myself",
+            200,
+            serde_json::json!({ "displayName": "GitHub Bot" }),
+        )
+        .await;
+        mock(
+            &server,
+            "/rest/api/3/field",
+            200,
+            serde_json::json!([
+                { "id": "customfield_10369", "name": "Checklist" },
+                { "id": "summary", "name": "Summary" }
+            ]),
+        )
+        .await;
+        mock(
+            &server,
+            "/rest/api/3/issue/ABC-1/editmeta",
+            200,
+            serde_json::json!({ "fields": { "summary": {} } }),
+        )
+        .await;
+
+        let mut report = Report::default();
+        check_site(
+            &mut report,
+            "default",
+            &client(&server),
+            &["customfield_10369".to_string(), "customfield_1".to_string()],
+            Some("ABC-1"),
+        )
+        .await;
+
+        assert!(!report.passed());
+        assert_eq!(
+            report.to_string(),
+            "PASS default credentials: authenticated as GitHub Bot\n\
+             PASS default field customfield_10369: exists as \"Checklist\"\n\
+             FAIL default field customfield_1: does not exist\n\
+             FAIL default field customfield_10369: not editable on ABC-1\n\
+             FAIL default field customfield_1: not editable on ABC-1\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_on_bad_credentials() {
+        let server = MockServer::start().await;
+        mock(
+            &server,
+            "/rest/api/3/myself",
+            401,
+            serde_json::json!({ "errorMessages": ["Unauthorized"] }),
+        )
+        .await;
+
+        let mut report = Report::default();
+        check_site(
+            &mut report,
+            "default",
+            &client(&server),
+            &["customfield_10369".to_string()],
+            None,
+        )
+        .await;
+
+        assert!(!report.passed());
+        assert_eq!(report.0.len(), 1);
+    }
+}
@@ -1,30 +1,6 @@
+use crate::jira::models::JiraErrorBody;
 use thiserror::Error;
 
-pub type LambdaResult = std::result::Result<lambda_http::Response<lambda_http::Body>, LambdaError>;
-
-pub trait LambdaResultExt {
-    fn ok_response() -> LambdaResult {
-        use lambda_http::{http::StatusCode, Body, Response};
-
-        Ok(Response::builder()
-            .status(StatusCode::OK)
-            .body(Body::Empty)
-            .expect("Response body can be built"))
-    }
-
-    fn not_ok_response() -> LambdaResult {
-        use lambda_http::{http::StatusCode, Body, Response};
-
-        Ok(Response::builder()
-            .status(StatusCode::NOT_FOUND)
-            .body(Body::Empty)
-            .expect("Response body can be built"))
-    }
-}
-impl LambdaResultExt for LambdaResult {}
-
-pub type LambdaError = lambda_http::Error;
-
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Error, Debug)]
@@ -38,12 +14,28 @@ pub enum Error {
     #[error("Missing webhook signature header")]
     MissingSignatureHeader,
 
+    #[error("No webhook secret configured for {0}")]
+    NoWebhookSecret(String),
+
+    #[error("Delivery of {size} bytes exceeds the limit of {limit} bytes")]
+    PayloadTooLarge { size: usize, limit: usize },
+
     #[error("Failed to deserialize payload")]
     PayloadDeserialization(#[from] serde_json::Error),
 
     #[error("Jira API error: {0}")]
     JiraApi(String),
 
+    #[error(transparent)]
+    Jira(Box<JiraError>),
+
+    #[error("GitHub API error: {operation} failed with {status}: {message}")]
+    GitHubApi {
+        operation: String,
+        status: reqwest::StatusCode,
+        message: String,
+    },
+
     #[error("Failed to generate url for request")]
     BadUrlGenerated(#[from] url::ParseError),
 
@@ -51,17 +43,145 @@ pub enum Error {
     HttpClient(#[from] reqwest::Error),
 
     #[error("Environment variable not set: {env_var_name}")]
-    EnvVarNotSet { env_var_name: &'static str },
+    EnvVarNotSet { env_var_name: String },
 
     #[error("Environment variable {env_var_name} has bad value")]
-    EnvVarBadValue { env_var_name: &'static str },
+    EnvVarBadValue { env_var_name: String },
+
+    #[cfg(feature = "aws-kms")]
+    #[error("AWS KMS error decrypting {env_var_name}: {source:?}")]
+    AwsKms {
+        env_var_name: String,
+        source: Box<aws_sdk_kms::Error>,
+    },
+
+    #[error("{env_var_name} was encrypted with unexpected KMS key {key_id}")]
+    KmsKeyMismatch {
+        env_var_name: String,
+        key_id: String,
+    },
+
+    #[cfg(feature = "aws-secrets")]
+    #[error("AWS Secrets Manager error: {0:?}")]
+    SecretsManager(#[from] Box<aws_sdk_secretsmanager::Error>),
+
+    #[cfg(feature = "aws-secrets")]
+    #[error("AWS SSM error: {0:?}")]
+    Ssm(#[from] Box<aws_sdk_ssm::Error>),
+
+    #[cfg(feature = "aws-dynamodb")]
+    #[error("AWS DynamoDB error: {0:?}")]
+    DynamoDb(#[from] Box<aws_sdk_dynamodb::Error>),
 
-    #[error("AWS KMS error: {0:?}")]
-    AwsKms(#[from] aws_sdk_kms::Error),
+    #[cfg(feature = "aws-sqs")]
+    #[error("AWS SQS error: {0:?}")]
+    Sqs(#[from] Box<aws_sdk_sqs::Error>),
+
+    #[cfg(feature = "aws-s3")]
+    #[error("AWS S3 error: {0:?}")]
+    S3(#[from] Box<aws_sdk_s3::Error>),
+
+    #[error("Delivery {0} is still being handled")]
+    DeliveryInProgress(String),
+
+    #[error("Delivery archive error: {0}")]
+    Archive(String),
+
+    #[error("Backfill checkpoint error: {0}")]
+    Checkpoint(String),
 
     #[error("Internal error: {0}")]
     Internal(String),
 
     #[error("Invalid event type {0}")]
     InvalidEventType(String),
+
+    #[error("Invalid configuration:{}", .0.iter().map(|problem| format!("\n- {problem}")).collect::<String>())]
+    InvalidConfig(Vec<Error>),
+
+    #[error("Invalid config file: {0}")]
+    InvalidConfigFile(String),
+
+    #[error("Credentials of Jira {0} contain characters not allowed in an HTTP header")]
+    InvalidJiraCredentials(String),
+
+    #[error("No routing rule matches repository {0}")]
+    UnknownRepository(String),
+
+    #[error("Unknown Jira site {0}")]
+    UnknownJiraSite(String),
+
+    #[cfg(feature = "server")]
+    #[error("TLS setup failed: {0}")]
+    Tls(String),
+
+    #[cfg(feature = "server")]
+    #[error("Server error: {0}")]
+    Server(#[from] std::io::Error),
+}
+
+impl From<JiraError> for Error {
+    fn from(error: JiraError) -> Self {
+        Error::Jira(Box::new(error))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JiraOperation {
+    GetIssue,
+    UpdateChecklist,
+    GetProperty,
+    SetProperty,
+    GetMyself,
+    GetFields,
+    GetEditMeta,
+    Search,
+}
+
+impl std::fmt::Display for JiraOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            JiraOperation::GetIssue => "get issue",
+            JiraOperation::UpdateChecklist => "update checklist",
+            JiraOperation::GetProperty => "get issue property",
+            JiraOperation::SetProperty => "set issue property",
+            JiraOperation::GetMyself => "get current user",
+            JiraOperation::GetFields => "get fields",
+            JiraOperation::GetEditMeta => "get edit metadata",
+            JiraOperation::Search => "search issues",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JiraErrorKind {
+    NotFound,
+    Permission,
+    Validation,
+    RateLimited,
+    Other,
+}
+
+/// A failed Jira REST API request, including the error details returned by Jira
+#[derive(Error, Debug)]
+#[error("Jira API error: {operation} for {issue_key} failed with {status}: {body}")]
+pub struct JiraError {
+    pub status: reqwest::StatusCode,
+    pub issue_key: String,
+    pub operation: JiraOperation,
+    pub body: JiraErrorBody,
+}
+
+impl JiraError {
+    pub fn kind(&self) -> JiraErrorKind {
+        use reqwest::StatusCode;
+
+        match self.status {
+            StatusCode::NOT_FOUND => JiraErrorKind::NotFound,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => JiraErrorKind::Permission,
+            StatusCode::BAD_REQUEST => JiraErrorKind::Validation,
+            StatusCode::TOO_MANY_REQUESTS => JiraErrorKind::RateLimited,
+            _ => JiraErrorKind::Other,
+        }
+    }
 }
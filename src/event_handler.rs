@@ -1,31 +1,61 @@
 use crate::error::Error;
 use crate::github::models::{extract_issue_key, PullRequest, PullRequestPayload};
-use crate::jira::{ChecklistManipulator, JiraClient, PrStatus};
+use crate::jira::property::PullRequestLink;
+use crate::jira::{ChecklistManipulator, IssueTracker, JiraRegistry, PrStatus};
+use crate::outcome::{IssueOutcome, IssueResult, Outcome};
+use crate::routing::{RoutingConfig, Rule};
 
-#[tracing::instrument(skip_all,fields(action = %payload.action, pull_request = %payload.pull_request.number))]
-pub async fn handle_pull_request_event(
+#[tracing::instrument(skip_all,fields(action = %payload.action, repository = %payload.repository.full_name, pull_request = %payload.pull_request.number))]
+pub async fn handle_pull_request_event<T: IssueTracker>(
     payload: PullRequestPayload,
-    jira_client: JiraClient,
+    jira: JiraRegistry<T>,
+    routing: &RoutingConfig,
     dry_run: bool,
-) -> Result<(), Error> {
+) -> Result<Outcome, Error> {
     tracing::info!("Processing pull_request event");
 
+    let rule = routing.route(&payload.repository.full_name)?;
+    let mut outcome = Outcome {
+        dry_run: rule.dry_run(dry_run),
+        ..Outcome::default()
+    };
+
     if let Some(issue_key) = extract_issue_key(&payload.pull_request.title) {
+        outcome.issue_keys.push(issue_key.clone());
+
+        if !rule.allows_issue(&issue_key) {
+            tracing::info!("Issue {issue_key} is not in an allowed project. Skip update.");
+            outcome.issues.push(IssueOutcome {
+                issue_key,
+                result: IssueResult::NotAllowed,
+                property_updated: false,
+            });
+            return Ok(outcome);
+        }
+
+        let issue_tracker = jira.site(routing.site_for(rule, &issue_key))?;
         let status = pr_status(&payload.pull_request);
-        update_issue(
-            &jira_client,
+        let issue_outcome = update_issue(
+            issue_tracker,
             &issue_key,
-            &payload.pull_request.html_url,
+            &payload,
+            rule,
             status,
-            dry_run,
+            outcome.dry_run,
         )
-        .await?
+        .await
+        .unwrap_or_else(|error| IssueOutcome {
+            issue_key,
+            result: IssueResult::Failed { error },
+            property_updated: false,
+        });
+        outcome.issues.push(issue_outcome);
     }
 
-    Ok(())
+    Ok(outcome)
 }
 
-fn pr_status(pr: &PullRequest) -> PrStatus {
+pub fn pr_status(pr: &PullRequest) -> PrStatus {
     if pr.merged {
         PrStatus::Merged
     } else if pr.state == "closed" {
@@ -35,59 +65,156 @@ fn pr_status(pr: &PullRequest) -> PrStatus {
     }
 }
 
-#[tracing::instrument(skip(jira_client, issue_key, html_url))]
-async fn update_issue(
-    jira_client: &JiraClient,
+fn pull_request_link(payload: &PullRequestPayload, status: PrStatus) -> PullRequestLink {
+    let pr = &payload.pull_request;
+    PullRequestLink {
+        repository: payload.repository.full_name.clone(),
+        number: pr.number,
+        url: pr.html_url.clone(),
+        status,
+        created_at: pr.created_at,
+        updated_at: pr.updated_at,
+        closed_at: pr.closed_at,
+        merged_at: pr.merged_at,
+    }
+}
+
+/// Applies the pull request of the payload to the checklist and the pull request property of the issue
+#[tracing::instrument(skip(issue_tracker, issue_key, payload, rule))]
+pub async fn update_issue<T: IssueTracker>(
+    issue_tracker: &T,
     issue_key: &str,
-    html_url: &str,
+    payload: &PullRequestPayload,
+    rule: &Rule,
     status: PrStatus,
     dry_run: bool,
-) -> Result<(), Error> {
+) -> Result<IssueOutcome, Error> {
     tracing::info!("Updating issue");
 
-    let issue = jira_client.get_issue(issue_key).await?;
+    // the property records the last applied event of every linked pull request
+    let mut links = issue_tracker.get_pull_requests(issue_key).await?;
+    let link = pull_request_link(payload, status);
+    if links.is_stale(&link, payload.action == "reopened") {
+        tracing::info!("Newer event of the pull request was already applied. Skip update.");
+        return Ok(IssueOutcome {
+            issue_key: issue_key.to_string(),
+            result: IssueResult::Stale,
+            property_updated: false,
+        });
+    }
 
-    let Some(checklist_text) = issue.fields.checklist.text() else {
-        tracing::warn!("No checklist found for {issue_key}. Skip update.");
-        return Ok(());
-    };
+    let checklist = updated_checklist(issue_tracker, issue_key, payload, rule, status).await?;
+    let links_changed = links.upsert(link);
 
-    let mut checklist = ChecklistManipulator::new(&checklist_text);
+    let outcome = IssueOutcome {
+        issue_key: issue_key.to_string(),
+        result: match checklist {
+            ChecklistChange::Missing => IssueResult::MissingChecklist,
+            ChecklistChange::MissingSection => IssueResult::MissingSection,
+            ChecklistChange::Unchanged => IssueResult::Unchanged,
+            ChecklistChange::Changed(_) => IssueResult::Updated,
+        },
+        property_updated: links_changed,
+    };
 
-    if !checklist.upsert_pr(html_url, status) {
-        tracing::debug!("checklist not updated, skip");
-        return Ok(());
+    let checklist = match checklist {
+        ChecklistChange::Changed(checklist) => Some(checklist),
+        _ => None,
+    };
+    if checklist.is_none() && !links_changed {
+        return Ok(outcome);
     }
 
     if dry_run {
-        tracing::info!("dry run mode. would have updated issue")
-    } else {
+        tracing::info!("dry run mode. would have updated issue");
+        return Ok(outcome);
+    }
+
+    if let Some(checklist) = checklist {
         tracing::debug!("Updating checklist");
-        jira_client
-            .update_checklist(issue_key, checklist.to_string())
+        issue_tracker
+            .update_checklist(issue_key, &rule.checklist_field, checklist)
             .await?
     }
 
-    Ok(())
+    if links_changed {
+        tracing::debug!("Updating pull request property");
+        issue_tracker.set_pull_requests(issue_key, &links).await?
+    }
+
+    Ok(outcome)
+}
+
+enum ChecklistChange {
+    Missing,
+    MissingSection,
+    Unchanged,
+    /// The new checklist text
+    Changed(String),
+}
+
+async fn updated_checklist<T: IssueTracker>(
+    issue_tracker: &T,
+    issue_key: &str,
+    payload: &PullRequestPayload,
+    rule: &Rule,
+    status: PrStatus,
+) -> Result<ChecklistChange, Error> {
+    let Some(checklist_text) = issue_tracker
+        .get_checklist(issue_key, &rule.checklist_field)
+        .await?
+    else {
+        tracing::warn!("No checklist found for {issue_key}. Skip update.");
+        return Ok(ChecklistChange::Missing);
+    };
+
+    let mut checklist = ChecklistManipulator::new(&checklist_text);
+    if !checklist.has_pr_section() {
+        tracing::warn!("Missing pull request section");
+        return Ok(ChecklistChange::MissingSection);
+    }
+
+    let entry = rule.render_entry(payload);
+    if !checklist.upsert_pr(&payload.pull_request.html_url, &entry, status) {
+        tracing::debug!("checklist not updated, skip");
+        return Ok(ChecklistChange::Unchanged);
+    }
+
+    Ok(ChecklistChange::Changed(checklist.to_string()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::github::models::{Changes, PullRequest, TitleChange};
+    use crate::github::models::{Changes, PullRequest, Repository, TitleChange};
+
+    fn pull_request(title: &str, state: &str, merged: bool) -> PullRequest {
+        PullRequest {
+            title: title.to_string(),
+            html_url: "https://github.com/org/repo/pull/1".to_string(),
+            number: 1,
+            state: state.to_string(),
+            merged,
+            created_at: "2026-10-18T10:00:00Z".parse().unwrap(),
+            updated_at: "2026-10-18T10:00:00Z".parse().unwrap(),
+            closed_at: None,
+            merged_at: None,
+        }
+    }
+
+    fn repository() -> Repository {
+        Repository {
+            full_name: "org/repo".to_string(),
+        }
+    }
 
     #[test]
     fn test_extract_issue_key_from_payload() {
         let payload = PullRequestPayload {
             action: "opened".to_string(),
-            pull_request: PullRequest {
-                title: "[ISSUE-123] Test PR".to_string(),
-                html_url: "https://github.com/org/repo/pull/1".to_string(),
-                number: 1,
-                state: "open".to_string(),
-                merged: false,
-            },
+            pull_request: pull_request("[ISSUE-123] Test PR", "open", false),
             changes: None,
+            repository: repository(),
         };
 
         let key = extract_issue_key(&payload.pull_request.title);
@@ -98,18 +225,13 @@ mod tests {
     fn test_extract_old_issue_key_from_edited_payload() {
         let payload = PullRequestPayload {
             action: "edited".to_string(),
-            pull_request: PullRequest {
-                title: "[ISSUE-234] Updated PR".to_string(),
-                html_url: "https://github.com/org/repo/pull/1".to_string(),
-                number: 1,
-                state: "open".to_string(),
-                merged: false,
-            },
+            pull_request: pull_request("[ISSUE-234] Updated PR", "open", false),
             changes: Some(Changes {
                 title: Some(TitleChange {
                     from: "[ISSUE-123] Original PR".to_string(),
                 }),
             }),
+            repository: repository(),
         };
 
         let current_key = extract_issue_key(&payload.pull_request.title);
@@ -125,37 +247,19 @@ mod tests {
 
     #[test]
     fn test_pr_status_open() {
-        let pr = PullRequest {
-            title: "Test".to_string(),
-            html_url: "https://github.com/org/repo/pull/1".to_string(),
-            number: 1,
-            state: "open".to_string(),
-            merged: false,
-        };
+        let pr = pull_request("Test", "open", false);
         assert_eq!(pr_status(&pr), PrStatus::Open);
     }
 
     #[test]
     fn test_pr_status_merged() {
-        let pr = PullRequest {
-            title: "Test".to_string(),
-            html_url: "https://github.com/org/repo/pull/1".to_string(),
-            number: 1,
-            state: "closed".to_string(),
-            merged: true,
-        };
+        let pr = pull_request("Test", "closed", true);
         assert_eq!(pr_status(&pr), PrStatus::Merged);
     }
 
     #[test]
     fn test_pr_status_closed() {
-        let pr = PullRequest {
-            title: "Test".to_string(),
-            html_url: "https://github.com/org/repo/pull/1".to_string(),
-            number: 1,
-            state: "closed".to_string(),
-            merged: false,
-        };
+        let pr = pull_request("Test", "closed", false);
         assert_eq!(pr_status(&pr), PrStatus::Closed);
     }
 }
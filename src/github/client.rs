@@ -0,0 +1,338 @@
+use crate::error::Error;
+use crate::github::models::{HookDelivery, PullRequest};
+use crate::secrets::SecretStore;
+use crate::types::Secret;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, LINK, USER_AGENT};
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::str::FromStr;
+
+const GITHUB_API_URL: &str = "GITHUB_API_URL";
+const GITHUB_TOKEN: &str = "GITHUB_TOKEN";
+const DEFAULT_API_URL: &str = "https://api.github.com/";
+
+/// A repository webhook `owner/repo:id` or an organization webhook `org:id`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hook {
+    pub owner: String,
+    pub repository: Option<String>,
+    pub id: u64,
+}
+
+impl Hook {
+    fn path(&self) -> String {
+        match &self.repository {
+            Some(repository) => format!("repos/{}/{repository}/hooks/{}", self.owner, self.id),
+            None => format!("orgs/{}/hooks/{}", self.owner, self.id),
+        }
+    }
+}
+
+impl FromStr for Hook {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid hook `{value}`, expected `owner/repo:id` or `org:id`");
+        let (origin, id) = value.trim().rsplit_once(':').ok_or_else(invalid)?;
+        let id = id.parse().map_err(|_| invalid())?;
+        let (owner, repository) = match origin.split_once('/') {
+            Some((owner, repository)) => (owner, Some(repository.to_string())),
+            None => (origin, None),
+        };
+        if owner.is_empty() || repository.as_ref().is_some_and(|r| r.is_empty()) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            owner: owner.to_string(),
+            repository,
+            id,
+        })
+    }
+}
+
+impl fmt::Display for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.repository {
+            Some(repository) => write!(f, "{}/{repository}:{}", self.owner, self.id),
+            None => write!(f, "{}:{}", self.owner, self.id),
+        }
+    }
+}
+
+/// One page of a paginated list, `next` is the URL of the following page
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next: Option<Url>,
+}
+
+/// GitHub REST API client authenticated with a token
+#[derive(Debug, Clone)]
+pub struct GitHubClient {
+    client: reqwest::Client,
+    base_url: Url,
+    auth_header: HeaderValue,
+}
+
+impl GitHubClient {
+    pub fn new(base_url: Url, token: &Secret) -> Result<Self, Error> {
+        let mut auth_header = HeaderValue::from_str(&format!("Bearer {}", token.expose()))
+            .map_err(|_| Error::EnvVarBadValue {
+                env_var_name: GITHUB_TOKEN.to_string(),
+            })?;
+        auth_header.set_sensitive(true);
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            base_url,
+            auth_header,
+        })
+    }
+
+    /// Token `GITHUB_TOKEN` from the secret store, the API at `GITHUB_API_URL` or api.github.com,
+    /// e.g. `https://github.example.com/api/v3/` for GitHub Enterprise Server
+    pub async fn from_env(secrets: &SecretStore) -> Result<Self, Error> {
+        let base_url =
+            std::env::var(GITHUB_API_URL).unwrap_or_else(|_| DEFAULT_API_URL.to_string());
+        // relative paths are joined to the base URL, so it has to end with a slash
+        let base_url = if base_url.ends_with('/') {
+            base_url
+        } else {
+            format!("{base_url}/")
+        };
+        let base_url = Url::parse(&base_url).map_err(|_| Error::EnvVarBadValue {
+            env_var_name: GITHUB_API_URL.to_string(),
+        })?;
+
+        Self::new(base_url, &secrets.get(GITHUB_TOKEN).await?)
+    }
+
+    fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, self.auth_header.clone());
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("application/vnd.github+json"),
+        );
+        headers.insert(
+            "X-GitHub-Api-Version",
+            HeaderValue::from_static("2022-11-28"),
+        );
+        headers.insert(USER_AGENT, HeaderValue::from_static("github-webhook"));
+        headers
+    }
+
+    async fn get_page<T: DeserializeOwned>(
+        &self,
+        url: Url,
+        operation: &str,
+    ) -> Result<Page<T>, Error> {
+        let response = self.client.get(url).headers(self.headers()).send().await?;
+        let response = check_response(response, operation).await?;
+        let next = response
+            .headers()
+            .get(LINK)
+            .and_then(|link| link.to_str().ok())
+            .and_then(next_link);
+        let items = response.json().await?;
+        Ok(Page { items, next })
+    }
+
+    /// Deliveries of the hook, newest first, `next` continues with the following page
+    pub async fn hook_deliveries(
+        &self,
+        hook: &Hook,
+        next: Option<Url>,
+    ) -> Result<Page<HookDelivery>, Error> {
+        let url = match next {
+            Some(url) => url,
+            None => self
+                .base_url
+                .join(&format!("{}/deliveries?per_page=100", hook.path()))?,
+        };
+        tracing::debug!(%hook, "Listing hook deliveries");
+        self.get_page(url, "list hook deliveries").await
+    }
+
+    pub async fn pull_request(&self, repository: &str, number: u64) -> Result<PullRequest, Error> {
+        let url = self
+            .base_url
+            .join(&format!("repos/{repository}/pulls/{number}"))?;
+        tracing::debug!(repository, number, "Fetching pull request");
+        let response = self.client.get(url).headers(self.headers()).send().await?;
+        let response = check_response(response, "get pull request").await?;
+        Ok(response.json().await?)
+    }
+
+    /// Pull requests of the repository in the state `open`, `closed` or `all`, oldest first, `next`
+    /// continues with the following page
+    pub async fn pull_requests(
+        &self,
+        repository: &str,
+        state: &str,
+        next: Option<Url>,
+    ) -> Result<Page<PullRequest>, Error> {
+        let url = match next {
+            Some(url) => url,
+            None => {
+                let mut url = self.base_url.join(&format!("repos/{repository}/pulls"))?;
+                url.query_pairs_mut()
+                    .append_pair("state", state)
+                    .append_pair("sort", "created")
+                    .append_pair("direction", "asc")
+                    .append_pair("per_page", "100");
+                url
+            }
+        };
+        tracing::debug!(repository, state, "Listing pull requests");
+        let mut page: Page<PullRequest> = self.get_page(url, "list pull requests").await?;
+        // the list only reports `merged_at`
+        for pull_request in &mut page.items {
+            pull_request.merged |= pull_request.merged_at.is_some();
+        }
+        Ok(page)
+    }
+
+    /// Asks GitHub to send the delivery again
+    pub async fn redeliver(&self, hook: &Hook, delivery_id: u64) -> Result<(), Error> {
+        let url = self.base_url.join(&format!(
+            "{}/deliveries/{delivery_id}/attempts",
+            hook.path()
+        ))?;
+        tracing::info!(%hook, delivery_id, "Redelivering");
+        let response = self.client.post(url).headers(self.headers()).send().await?;
+        check_response(response, "redeliver").await?;
+        Ok(())
+    }
+}
+
+/// URL of `rel="next"` in a `Link` header
+fn next_link(link: &str) -> Option<Url> {
+    link.split(',').find_map(|part| {
+        let (url, rel) = part.split_once(';')?;
+        if rel.trim() != r#"rel="next""# {
+            return None;
+        }
+        Url::parse(url.trim().strip_prefix('<')?.strip_suffix('>')?).ok()
+    })
+}
+
+async fn check_response(
+    response: reqwest::Response,
+    operation: &str,
+) -> Result<reqwest::Response, Error> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    let message = body["message"].as_str().unwrap_or_default().to_string();
+    tracing::error!(
+        status = status.as_u16(),
+        operation,
+        message,
+        "GitHub API request failed"
+    );
+    Err(Error::GitHubApi {
+        operation: operation.to_string(),
+        status,
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn client(server: &MockServer) -> GitHubClient {
+        GitHubClient::new(
+            Url::parse(&format!("{}/", server.uri())).unwrap(),
+            &Secret::from("token".to_string()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_hooks() {
+        let hook: Hook = "example-org/widgets:12345".parse().unwrap();
+        assert_eq!(hook.path(), "repos/example-org/widgets/hooks/12345");
+        assert_eq!(hook.to_string(), "example-org/widgets:12345");
+
+        let hook: Hook = "example-org:678".parse().unwrap();
+        assert_eq!(hook.path(), "orgs/example-org/hooks/678");
+
+        "example-org/widgets".parse::<Hook>().unwrap_err();
+        "example-org/:1".parse::<Hook>().unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn follows_next_link() {
+        let server = MockServer::start().await;
+        let next = format!(
+            "{}/repos/example-org/widgets/hooks/1/deliveries?per_page=100&cursor=v1_2",
+            server.uri()
+        );
+        Mock::given(method("GET"))
+            .and(path("/repos/example-org/widgets/hooks/1/deliveries"))
+            .and(header("authorization", "Bearer token"))
+            .and(query_param("cursor", "v1_2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/example-org/widgets/hooks/1/deliveries"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("link", format!(r#"<{next}>; rel="next""#).as_str())
+                    .set_body_json(serde_json::json!([{
+                        "id": 3,
+                        "guid": "guid-3",
+                        "delivered_at": "2026-10-18T10:00:00Z",
+                        "redelivery": false,
+                        "status": "OK",
+                        "status_code": 200,
+                        "event": "pull_request",
+                        "action": "opened"
+                    }])),
+            )
+            .mount(&server)
+            .await;
+
+        let client = client(&server);
+        let hook = "example-org/widgets:1".parse().unwrap();
+        let page = client.hook_deliveries(&hook, None).await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.next.as_ref().map(Url::as_str), Some(next.as_str()));
+
+        let page = client.hook_deliveries(&hook, page.next).await.unwrap();
+        assert!(page.items.is_empty());
+        assert!(page.next.is_none());
+    }
+
+    #[tokio::test]
+    async fn reports_api_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(
+                "/repos/example-org/widgets/hooks/1/deliveries/3/attempts",
+            ))
+            .respond_with(
+                ResponseTemplate::new(404)
+                    .set_body_json(serde_json::json!({"message": "Not Found"})),
+            )
+            .mount(&server)
+            .await;
+
+        let error = client(&server)
+            .redeliver(&"example-org/widgets:1".parse().unwrap(), 3)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "GitHub API error: redeliver failed with 404 Not Found: Not Found"
+        );
+    }
+}
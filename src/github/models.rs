@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -5,6 +6,7 @@ pub struct PullRequestPayload {
     pub action: String,
     pub pull_request: PullRequest,
     pub changes: Option<Changes>,
+    pub repository: Repository,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -15,6 +17,27 @@ pub struct PullRequest {
     pub state: String,
     #[serde(default)]
     pub merged: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub merged_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Repository {
+    pub full_name: String,
+}
+
+/// Origin of a delivery as claimed by its payload, must not be trusted before the signature is verified
+#[derive(Debug, Default, Deserialize)]
+pub struct UnverifiedOrigin {
+    pub repository: Option<Repository>,
+    pub organization: Option<Organization>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Organization {
+    pub login: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -27,15 +50,35 @@ pub struct TitleChange {
     pub from: String,
 }
 
+/// A delivery of a webhook as listed by the hook deliveries API
+#[derive(Debug, Clone, Deserialize)]
+pub struct HookDelivery {
+    pub id: u64,
+    /// Shared by a delivery and its redeliveries, sent as `X-GitHub-Delivery`
+    pub guid: String,
+    pub delivered_at: DateTime<Utc>,
+    pub redelivery: bool,
+    pub status: String,
+    /// `0` if the hook could not be reached
+    pub status_code: u16,
+    pub event: String,
+    pub action: Option<String>,
+}
+
+impl HookDelivery {
+    pub fn failed(&self) -> bool {
+        !(200..300).contains(&self.status_code)
+    }
+}
+
 pub fn extract_issue_key(title: &str) -> Option<String> {
     let re = regex::Regex::new(r"^\[?([A-Za-z]+)[\- ]*([0-9]+)").unwrap();
 
-    re.captures(title)
-        .and_then(|captures| {
-            let prefix = captures.get(1)?.as_str().to_uppercase();
-            let number = captures.get(2)?.as_str();
-            Some(format!("{}-{}", prefix, number))
-        })
+    re.captures(title).and_then(|captures| {
+        let prefix = captures.get(1)?.as_str().to_uppercase();
+        let number = captures.get(2)?.as_str();
+        Some(format!("{}-{}", prefix, number))
+    })
 }
 
 #[cfg(test)]
@@ -49,7 +92,6 @@ mod tests {
         assert_eq!(key, Some("ISSUE-123".to_string()));
     }
 
-
     #[test]
     fn test_extract_issue_key_none() {
         let title = "This is a test PR without issue keys";
@@ -1,15 +1,36 @@
 use crate::error::Error;
-use crate::types::WebhookSecret;
+use crate::github::models::UnverifiedOrigin;
+use crate::metrics;
+use crate::types::{WebhookSecret, WebhookSecretMap, WebhookSecrets};
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
 
 type HmacSha256 = Hmac<Sha256>;
 
+/// Selects the secrets of a delivery by the origin claimed in the still unverified payload
+pub fn select_secrets<'a>(
+    payload: &[u8],
+    secrets: &'a WebhookSecretMap,
+) -> Result<&'a WebhookSecrets, Error> {
+    if !secrets.is_keyed() {
+        return secrets.select(None, None);
+    }
+
+    // an unparsable payload fails signature validation or deserialization later on
+    let origin: UnverifiedOrigin = serde_json::from_slice(payload).unwrap_or_default();
+
+    secrets.select(
+        origin.repository.as_ref().map(|r| r.full_name.as_str()),
+        origin.organization.as_ref().map(|o| o.login.as_str()),
+    )
+}
+
+/// Returns the index of the secret that matched
 pub fn validate_signature(
     payload: &[u8],
     signature_header: Option<&str>,
-    secret: &WebhookSecret,
-) -> Result<(), Error> {
+    secrets: &WebhookSecrets,
+) -> Result<usize, Error> {
     let signature = signature_header.ok_or(Error::MissingSignatureHeader)?;
 
     let signature = signature
@@ -18,17 +39,62 @@ pub fn validate_signature(
 
     let signature_bytes = hex::decode(signature).map_err(|_| Error::InvalidWebhookSignature)?;
 
+    for (index, secret) in secrets.iter().enumerate() {
+        if mac(payload, secret)?
+            .verify_slice(&signature_bytes)
+            .is_err()
+        {
+            continue;
+        }
+
+        tracing::info!(secret_index = index, "Signature verified");
+        if index > 0 {
+            tracing::warn!(
+                secret_index = index,
+                "Signature verified with deprecated webhook secret"
+            );
+            metrics::count("DeprecatedWebhookSecretUsed", &[]);
+        }
+
+        return Ok(index);
+    }
+
+    Err(Error::InvalidWebhookSignature)
+}
+
+/// The `X-Hub-Signature-256` header GitHub sends along with the payload
+pub fn sign(payload: &[u8], secret: &WebhookSecret) -> Result<String, Error> {
+    let signature = mac(payload, secret)?.finalize().into_bytes();
+    Ok(format!("sha256={}", hex::encode(signature)))
+}
+
+fn mac(payload: &[u8], secret: &WebhookSecret) -> Result<HmacSha256, Error> {
     let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
         .map_err(|_| Error::Internal("Failed to create HMAC".to_string()))?;
-
     mac.update(payload);
+    Ok(mac)
+}
 
-    mac.verify_slice(&signature_bytes)
-        .map_err(|_| Error::InvalidWebhookSignature)?;
-
-    tracing::trace!("Signature verified");
+/// Secret the deliveries built by `signed_request` are signed with in tests
+#[cfg(test)]
+pub(crate) const TEST_SECRET: &str = "It's a Secret to Everybody";
 
-    Ok(())
+/// A delivery as GitHub sends it, signed with `secret`
+#[cfg(test)]
+pub(crate) fn signed_request(
+    event_type: &str,
+    delivery_id: &str,
+    body: String,
+    secret: &str,
+) -> http::Request<String> {
+    let signature = sign(body.as_bytes(), &WebhookSecret::new(secret).unwrap()).unwrap();
+    http::Request::builder()
+        .method("POST")
+        .header("X-GitHub-Event", event_type)
+        .header("X-GitHub-Delivery", delivery_id)
+        .header("X-Hub-Signature-256", signature)
+        .body(body)
+        .unwrap()
 }
 
 #[cfg(test)]
@@ -37,7 +103,9 @@ mod tests {
 
     #[test]
     fn test_validate_signature_valid() {
-        let secret = WebhookSecret::new(b"It's a Secret to Everybody").unwrap();
+        let secret = WebhookSecret::new(b"It's a Secret to Everybody")
+            .unwrap()
+            .into();
         let payload = b"Hello, World!";
         let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
 
@@ -46,7 +114,7 @@ mod tests {
 
     #[test]
     fn test_validate_signature_invalid() {
-        let secret = WebhookSecret::new("test_secret").unwrap();
+        let secret = WebhookSecret::new("test_secret").unwrap().into();
         let payload = b"test_payload";
 
         let signature = "sha256=invalid_signature";
@@ -54,9 +122,35 @@ mod tests {
         validate_signature(payload, Some(signature), &secret).expect_err("Expected error");
     }
 
+    #[test]
+    fn test_validate_signature_any_secret() {
+        let secrets = WebhookSecrets::new(vec![
+            WebhookSecret::new("new_secret").unwrap(),
+            WebhookSecret::new(b"It's a Secret to Everybody").unwrap(),
+        ])
+        .unwrap();
+        let payload = b"Hello, World!";
+        let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+
+        assert_eq!(
+            validate_signature(payload, Some(signature), &secrets).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_sign() {
+        let secret = WebhookSecret::new(b"It's a Secret to Everybody").unwrap();
+
+        assert_eq!(
+            sign(b"Hello, World!", &secret).unwrap(),
+            "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+        );
+    }
+
     #[test]
     fn test_validate_signature_missing_header() {
-        let secret = WebhookSecret::new("test_secret").unwrap();
+        let secret = WebhookSecret::new("test_secret").unwrap().into();
         let payload = b"test_payload";
 
         validate_signature(payload, None, &secret).expect_err("Expected error");
@@ -1,80 +1,1060 @@
+use crate::deliveries::{Claim, DeliveryStore};
 use crate::error::*;
-use crate::types::{WebhookEventType, WebhookSecret};
-use crate::{github, jira};
+use crate::github;
+use crate::jira::{IssueTracker, JiraRegistry};
+use crate::outcome::Outcome;
+use crate::queue::{DeliveryQueue, QueuedDelivery};
+use crate::routing::RoutingConfig;
+use crate::types::{WebhookEventType, WebhookSecretMap};
+use http::StatusCode;
 
-pub(crate) async fn function_handler(
-    jira_client: jira::JiraClient,
-    webhook_secret: WebhookSecret,
-    event: lambda_http::Request,
+/// Headers and body of a delivery as received by the Lambda or the server adapter
+#[derive(Clone, Copy)]
+pub struct WebhookRequest<'a> {
+    pub headers: &'a http::HeaderMap,
+    pub body: &'a [u8],
+}
+
+impl<'a> WebhookRequest<'a> {
+    fn header(&self, name: &str) -> Option<&'a str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+
+    /// Unique ID GitHub assigns to every delivery, kept on redelivery
+    pub fn delivery_id(&self) -> Option<&'a str> {
+        self.header("X-GitHub-Delivery")
+    }
+}
+
+impl<'a, B: AsRef<[u8]>> From<&'a http::Request<B>> for WebhookRequest<'a> {
+    fn from(request: &'a http::Request<B>) -> Self {
+        Self {
+            headers: request.headers(),
+            body: request.body().as_ref(),
+        }
+    }
+}
+
+/// Handles a webhook delivery independent of the runtime it was received by
+pub async fn function_handler<T: IssueTracker>(
+    jira: JiraRegistry<T>,
+    webhook_secrets: &WebhookSecretMap,
+    deliveries: &dyn DeliveryStore,
+    event: WebhookRequest<'_>,
+    routing: &RoutingConfig,
+    dry_run: bool,
+) -> Result<Outcome> {
+    verify(webhook_secrets, event)?;
+    handle_verified(jira, deliveries, event, routing, dry_run).await
+}
+
+/// Handles a delivery without checking its signature, only for deliveries that were verified
+/// before or are deliberately replayed unverified
+pub async fn handle_verified<T: IssueTracker>(
+    jira: JiraRegistry<T>,
+    deliveries: &dyn DeliveryStore,
+    event: WebhookRequest<'_>,
+    routing: &RoutingConfig,
     dry_run: bool,
-) -> Result<()> {
-    let signature = event
-        .headers()
-        .get("X-Hub-Signature-256")
-        .and_then(|v| v.to_str().ok());
-
-    use lambda_http::Body;
-    let body_bytes = match event.body() {
-        Body::Text(text) => text.as_bytes(),
-        Body::Binary(bytes) => bytes,
-        Body::Empty => &[],
+) -> Result<Outcome> {
+    let Some(delivery_id) = event.delivery_id() else {
+        tracing::warn!("Delivery without X-GitHub-Delivery header, can't detect duplicates");
+        return handle_event(jira, event, routing, dry_run).await;
     };
 
-    github::validate_signature(body_bytes, signature, &webhook_secret)?;
+    match deliveries.claim(delivery_id).await? {
+        Claim::Claimed => {}
+        Claim::Done => {
+            tracing::info!(delivery_id, "Duplicate delivery, skip");
+            return Ok(Outcome {
+                duplicate: true,
+                ..Outcome::default()
+            });
+        }
+        // the running attempt may still fail, so the redelivery must not be acknowledged
+        Claim::InProgress => return Err(Error::DeliveryInProgress(delivery_id.to_string())),
+    }
 
-    let event_type = event
-        .headers()
-        .get("X-GitHub-Event")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
+    let result = handle_event(jira, event, routing, dry_run).await;
+
+    // a redelivery of a failed delivery has to be handled again
+    let failed = result
+        .as_ref()
+        .map_or(true, |outcome| outcome.error().is_some());
+    if failed {
+        if let Err(error) = deliveries.release(delivery_id).await {
+            tracing::error!(delivery_id, ?error, "Failed to release delivery");
+        }
+    } else if let Err(error) = deliveries.complete(delivery_id).await {
+        tracing::error!(delivery_id, ?error, "Failed to complete delivery");
+    }
+
+    result
+}
+
+/// Verifies the delivery and hands it to the queue, the worker handles it later on
+pub async fn enqueue_handler(
+    webhook_secrets: &WebhookSecretMap,
+    queue: &dyn DeliveryQueue,
+    event: WebhookRequest<'_>,
+) -> Result<Outcome> {
+    verify(webhook_secrets, event)?;
+
+    queue.enqueue(&QueuedDelivery::new(event)).await?;
+    tracing::info!(delivery_id = event.delivery_id(), "Enqueued delivery");
+
+    Ok(Outcome {
+        queued: true,
+        ..Outcome::default()
+    })
+}
+
+fn verify(webhook_secrets: &WebhookSecretMap, event: WebhookRequest<'_>) -> Result<()> {
+    let signature = event.header("X-Hub-Signature-256");
+
+    let secrets = github::select_secrets(event.body, webhook_secrets)?;
+    github::validate_signature(event.body, signature, secrets)?;
+    Ok(())
+}
+
+async fn handle_event<T: IssueTracker>(
+    jira: JiraRegistry<T>,
+    event: WebhookRequest<'_>,
+    routing: &RoutingConfig,
+    dry_run: bool,
+) -> Result<Outcome> {
+    let event_type = event.header("X-GitHub-Event").unwrap_or("");
 
     match WebhookEventType::from_str(event_type) {
-        WebhookEventType::Ping => Ok(()),
+        WebhookEventType::Ping => Ok(Outcome::default()),
         WebhookEventType::PullRequest => {
-            let payload = serde_json::from_slice(body_bytes)?;
-            crate::event_handler::handle_pull_request_event(payload, jira_client, dry_run).await
+            let payload = serde_json::from_slice(event.body)?;
+            crate::event_handler::handle_pull_request_event(payload, jira, routing, dry_run).await
         }
         WebhookEventType::Other(event_type) => Err(Error::InvalidEventType(event_type)),
     }
 }
 
-pub(crate) fn result_to_http_reponse(response: Result<()>) -> LambdaResult {
-    let Err(error) = response else {
-        return LambdaResult::ok_response();
+/// Response to a delivery, a JSON body with the delivery ID, the outcome and for failures an
+/// error code
+pub fn response(result: Result<Outcome>, delivery_id: Option<&str>) -> http::Response<String> {
+    let outcome = match result {
+        Ok(outcome) => outcome,
+        Err(error) => {
+            let (status, code) = classify(&error);
+            log_error(&error, status);
+            return error_response(status, code, delivery_id);
+        }
+    };
+
+    let mut body = serde_json::to_value(&outcome).expect("Outcome can be serialized");
+    body["delivery_id"] = serde_json::json!(delivery_id);
+    let status = match outcome.error() {
+        None if outcome.queued => StatusCode::ACCEPTED,
+        None => StatusCode::OK,
+        Some(error) => {
+            let (status, code) = classify(error);
+            log_error(error, status);
+            body["error"] = serde_json::json!(code);
+            status
+        }
     };
+    json_response(status, body)
+}
+
+/// Whether a failed delivery may succeed when retried, i.e. it would be answered with a server
+/// error
+pub fn is_retryable(result: &Result<Outcome>) -> bool {
+    retryable_error(result).is_some()
+}
+
+/// Logs the error of a failed delivery like `response` does
+pub fn log_failure(result: &Result<Outcome>) {
+    if let Some(error) = failure(result) {
+        log_error(error, classify(error).0);
+    }
+}
 
-    tracing::trace!(?error, "function handler error occurred");
+/// Error and error code of a delivery that failed in a way that may succeed when retried
+pub fn retryable_error(result: &Result<Outcome>) -> Option<(&Error, &'static str)> {
+    let error = failure(result)?;
+    let (status, code) = classify(error);
+    status.is_server_error().then_some((error, code))
+}
+
+fn failure(result: &Result<Outcome>) -> Option<&Error> {
+    match result {
+        Ok(outcome) => outcome.error(),
+        Err(error) => Some(error),
+    }
+}
 
+pub fn error_response(
+    status: StatusCode,
+    code: &str,
+    delivery_id: Option<&str>,
+) -> http::Response<String> {
+    json_response(
+        status,
+        serde_json::json!({ "error": code, "delivery_id": delivery_id }),
+    )
+}
+
+fn json_response(status: StatusCode, body: serde_json::Value) -> http::Response<String> {
+    http::Response::builder()
+        .status(status)
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string())
+        .expect("Response body can be built")
+}
+
+/// Status and error code reported to GitHub for the error
+pub fn classify(error: &Error) -> (StatusCode, &'static str) {
     use crate::error::Error::*;
     match error {
-        // configuration mistakes, we should never see those here
-        BadUrlGenerated(_) | EmptyWebhookSecret | EnvVarNotSet { .. } | EnvVarBadValue { .. } => {
-            panic!("Configuration error: {:?}", error);
-        }
+        // configuration mistakes, should have been reported at startup
+        BadUrlGenerated(_)
+        | EmptyWebhookSecret
+        | EnvVarNotSet { .. }
+        | EnvVarBadValue { .. }
+        | KmsKeyMismatch { .. }
+        | InvalidConfig(_)
+        | InvalidConfigFile(_)
+        | InvalidJiraCredentials(_) => (StatusCode::INTERNAL_SERVER_ERROR, "configuration_error"),
+        #[cfg(feature = "server")]
+        Tls(_) | Server(_) => (StatusCode::INTERNAL_SERVER_ERROR, "configuration_error"),
 
         // possible configuration mistake on GitHub
-        InvalidEventType(event_type) => {
-            tracing::warn!("Received event type that was unexpected: {}", event_type);
+        InvalidEventType(_) => (StatusCode::ACCEPTED, "ignored_event_type"),
 
-            // return 200, because technically everything went well
-            return LambdaResult::ok_response();
-        }
+        // repository is not configured for this deployment
+        UnknownRepository(_) => (StatusCode::ACCEPTED, "unknown_repository"),
+
+        // issue resolved to a site that is not configured
+        UnknownJiraSite(_) => (StatusCode::INTERNAL_SERVER_ERROR, "configuration_error"),
 
         // request validation errors
-        MissingSignatureHeader | InvalidWebhookSignature | PayloadDeserialization(_) => {
-            tracing::warn!("Request validation error: {:?}", error);
-        }
+        MissingSignatureHeader => (StatusCode::UNAUTHORIZED, "missing_signature"),
+        InvalidWebhookSignature => (StatusCode::UNAUTHORIZED, "invalid_signature"),
+        NoWebhookSecret(_) => (StatusCode::UNAUTHORIZED, "no_webhook_secret"),
+        PayloadDeserialization(_) => (StatusCode::BAD_REQUEST, "invalid_payload"),
+        PayloadTooLarge { .. } => (StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large"),
 
         // API errors
-        JiraApi(_) | HttpClient(_) | AwsKms(_) => {
-            tracing::error!("API error: {:?}", error);
+        Jira(jira_error) => match jira_error.kind() {
+            // retrying doesn't help until the issue key, the checklist or the permissions are fixed
+            JiraErrorKind::NotFound | JiraErrorKind::Validation | JiraErrorKind::Permission => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "jira_rejected")
+            }
+            JiraErrorKind::RateLimited => (StatusCode::SERVICE_UNAVAILABLE, "jira_unavailable"),
+            JiraErrorKind::Other
+                if jira_error.status == reqwest::StatusCode::SERVICE_UNAVAILABLE =>
+            {
+                (StatusCode::SERVICE_UNAVAILABLE, "jira_unavailable")
+            }
+            JiraErrorKind::Other => (StatusCode::BAD_GATEWAY, "jira_error"),
+        },
+        JiraApi(_) => (StatusCode::BAD_GATEWAY, "jira_error"),
+        GitHubApi { .. } => (StatusCode::BAD_GATEWAY, "github_error"),
+        HttpClient(http_error) => {
+            if http_error.is_timeout() || http_error.is_connect() {
+                (StatusCode::SERVICE_UNAVAILABLE, "upstream_unavailable")
+            } else {
+                (StatusCode::BAD_GATEWAY, "upstream_error")
+            }
         }
+        #[cfg(feature = "aws-kms")]
+        AwsKms { .. } => (StatusCode::BAD_GATEWAY, "secrets_error"),
+        #[cfg(feature = "aws-secrets")]
+        SecretsManager(_) | Ssm(_) => (StatusCode::BAD_GATEWAY, "secrets_error"),
+        #[cfg(feature = "aws-dynamodb")]
+        DynamoDb(_) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "delivery_store_unavailable",
+        ),
+        #[cfg(feature = "aws-sqs")]
+        Sqs(_) => (StatusCode::SERVICE_UNAVAILABLE, "queue_unavailable"),
+        #[cfg(feature = "aws-s3")]
+        S3(_) => (StatusCode::SERVICE_UNAVAILABLE, "archive_unavailable"),
+        // another attempt is still running, a retry settles it
+        DeliveryInProgress(_) => (StatusCode::SERVICE_UNAVAILABLE, "delivery_in_progress"),
+        Archive(_) => (StatusCode::INTERNAL_SERVER_ERROR, "archive_error"),
+        Checkpoint(_) => (StatusCode::INTERNAL_SERVER_ERROR, "checkpoint_error"),
 
         // other kinds of errors
-        Internal(_) => {
-            tracing::error!("Internal error: {:?}", error);
+        Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+    }
+}
+
+fn log_error(error: &Error, status: StatusCode) {
+    match error {
+        Error::Jira(jira_error) => tracing::error!(
+            status = jira_error.status.as_u16(),
+            issue_key = jira_error.issue_key,
+            operation = %jira_error.operation,
+            kind = ?jira_error.kind(),
+            "API error: {}",
+            jira_error
+        ),
+        _ if status.is_server_error() => tracing::error!("Delivery failed: {:?}", error),
+        _ => tracing::warn!("Delivery rejected: {:?}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deliveries::MemoryDeliveryStore;
+    use crate::github::{signed_request, TEST_SECRET as SECRET};
+    use crate::jira::fake::FakeIssueTracker;
+    use crate::outcome::IssueResult;
+    use crate::routing::DEFAULT_SITE;
+    use crate::types::WebhookSecret;
+
+    const CHECKLIST: &str =
+        "# Development Process\n-! Task 1: Create Pull Request\n# Pull Requests";
+
+    fn fixture(name: &str) -> String {
+        let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+        std::fs::read_to_string(path).expect("fixture exists")
+    }
+
+    fn request(event_type: &str, body: String) -> http::Request<String> {
+        signed_request(
+            event_type,
+            "72d3162e-cc78-11e3-81ab-4c9367dc0958",
+            body,
+            SECRET,
+        )
+    }
+
+    fn registry(tracker: &FakeIssueTracker) -> JiraRegistry<FakeIssueTracker> {
+        JiraRegistry::from_iter([(DEFAULT_SITE.to_string(), tracker.clone())])
+    }
+
+    async fn handle(
+        tracker: &FakeIssueTracker,
+        event_type: &str,
+        fixture_name: &str,
+    ) -> Result<Outcome> {
+        handle_routed(tracker, event_type, fixture_name, &RoutingConfig::default()).await
+    }
+
+    async fn handle_routed(
+        tracker: &FakeIssueTracker,
+        event_type: &str,
+        fixture_name: &str,
+        routing: &RoutingConfig,
+    ) -> Result<Outcome> {
+        function_handler(
+            registry(tracker),
+            &WebhookSecret::new(SECRET).unwrap().into(),
+            &MemoryDeliveryStore::default(),
+            (&request(event_type, fixture(fixture_name))).into(),
+            routing,
+            false,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn opened_pull_request_is_added_to_checklist() {
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+
+        handle(&tracker, "pull_request", "pull_request_opened.json")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            tracker.checklist("ABC-123").unwrap(),
+            format!("{CHECKLIST}\n- https://github.com/example-org/widgets/pull/42")
+        );
+    }
+
+    #[tokio::test]
+    async fn opened_pull_request_is_recorded_in_property() {
+        use crate::jira::PrStatus;
+
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+
+        handle(&tracker, "pull_request", "pull_request_opened.json")
+            .await
+            .unwrap();
+
+        let links = tracker.pull_requests("ABC-123");
+        let link = links.find("example-org/widgets", 42).unwrap();
+        assert_eq!(link.url, "https://github.com/example-org/widgets/pull/42");
+        assert_eq!(link.status, PrStatus::Open);
+        assert_eq!(link.updated_at.to_rfc3339(), "2026-10-18T10:00:00+00:00");
+    }
+
+    #[tokio::test]
+    async fn property_is_recorded_without_checklist() {
+        use crate::jira::PrStatus;
+
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", None);
+
+        handle(&tracker, "pull_request", "pull_request_merged.json")
+            .await
+            .unwrap();
+
+        let links = tracker.pull_requests("ABC-123");
+        let link = links.find("example-org/widgets", 42).unwrap();
+        assert_eq!(link.status, PrStatus::Merged);
+        assert!(link.merged_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn merged_pull_request_updates_entry() {
+        let tracker = FakeIssueTracker::default().with_issue(
+            "ABC-123",
+            Some(&format!(
+                "{CHECKLIST}\n- https://github.com/example-org/widgets/pull/42"
+            )),
+        );
+
+        handle(&tracker, "pull_request", "pull_request_merged.json")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            tracker.checklist("ABC-123").unwrap(),
+            format!("{CHECKLIST}\n+ https://github.com/example-org/widgets/pull/42")
+        );
+    }
+
+    #[tokio::test]
+    async fn closed_pull_request_updates_entry() {
+        let tracker = FakeIssueTracker::default().with_issue(
+            "ABC-123",
+            Some(&format!(
+                "{CHECKLIST}\n- https://github.com/example-org/widgets/pull/42"
+            )),
+        );
+
+        handle(&tracker, "pull_request", "pull_request_closed.json")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            tracker.checklist("ABC-123").unwrap(),
+            format!("{CHECKLIST}\nx https://github.com/example-org/widgets/pull/42")
+        );
+    }
+
+    #[tokio::test]
+    async fn edited_pull_request_links_new_issue() {
+        let tracker = FakeIssueTracker::default()
+            .with_issue("ABC-123", Some(CHECKLIST))
+            .with_issue("ABC-234", Some(CHECKLIST));
+
+        handle(&tracker, "pull_request", "pull_request_edited.json")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            tracker.updates(),
+            vec![(
+                "ABC-234".to_string(),
+                format!("{CHECKLIST}\n- https://github.com/example-org/widgets/pull/42")
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn unchanged_checklist_is_not_written() {
+        let tracker = FakeIssueTracker::default().with_issue(
+            "ABC-123",
+            Some(&format!(
+                "{CHECKLIST}\n- https://github.com/example-org/widgets/pull/42"
+            )),
+        );
+
+        handle(&tracker, "pull_request", "pull_request_opened.json")
+            .await
+            .unwrap();
+
+        assert!(tracker.updates().is_empty());
+    }
+
+    #[tokio::test]
+    async fn missing_pull_request_section_is_not_written() {
+        let tracker = FakeIssueTracker::default()
+            .with_issue("ABC-123", Some("# Development Process\n-! Task 1"));
+
+        handle(&tracker, "pull_request", "pull_request_opened.json")
+            .await
+            .unwrap();
+
+        assert!(tracker.updates().is_empty());
+    }
+
+    #[tokio::test]
+    async fn empty_checklist_is_not_written() {
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", None);
+
+        handle(&tracker, "pull_request", "pull_request_opened.json")
+            .await
+            .unwrap();
+
+        assert!(tracker.updates().is_empty());
+    }
+
+    #[tokio::test]
+    async fn title_without_issue_key_is_ignored() {
+        let tracker = FakeIssueTracker::default();
+
+        handle(
+            &tracker,
+            "pull_request",
+            "pull_request_without_issue_key.json",
+        )
+        .await
+        .unwrap();
+
+        assert!(tracker.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn dry_run_does_not_write() {
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+
+        let outcome = function_handler(
+            registry(&tracker),
+            &WebhookSecret::new(SECRET).unwrap().into(),
+            &MemoryDeliveryStore::default(),
+            (&request("pull_request", fixture("pull_request_opened.json"))).into(),
+            &RoutingConfig::default(),
+            true,
+        )
+        .await
+        .unwrap();
+
+        assert!(outcome.dry_run);
+        assert_eq!(results(&outcome), [("ABC-123", "updated".into())]);
+        assert!(tracker.updates().is_empty());
+        assert_eq!(tracker.checklist("ABC-123").unwrap(), CHECKLIST);
+        assert!(tracker.pull_requests("ABC-123").pull_requests.is_empty());
+    }
+
+    #[tokio::test]
+    async fn ping_does_not_touch_jira() {
+        let tracker = FakeIssueTracker::default();
+
+        handle(&tracker, "ping", "ping.json").await.unwrap();
+
+        assert!(tracker.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn invalid_signature_is_rejected() {
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+
+        let error = function_handler(
+            registry(&tracker),
+            &WebhookSecret::new("another secret").unwrap().into(),
+            &MemoryDeliveryStore::default(),
+            (&request("pull_request", fixture("pull_request_opened.json"))).into(),
+            &RoutingConfig::default(),
+            false,
+        )
+        .await
+        .expect_err("Expected error");
+
+        assert!(matches!(error, Error::InvalidWebhookSignature));
+        assert!(tracker.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn routing_rule_selects_field_and_template() {
+        use crate::jira::fake::Call;
+
+        let routing = RoutingConfig::from_toml(
+            r#"
+            [[rule]]
+            organization = "example-org"
+            checklist_field = "customfield_10100"
+            entry_template = "{url} {title}"
+            "#,
+        )
+        .unwrap();
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+
+        handle_routed(
+            &tracker,
+            "pull_request",
+            "pull_request_opened.json",
+            &routing,
+        )
+        .await
+        .unwrap();
+
+        assert!(tracker.calls().contains(&Call::UpdateChecklist(
+            "ABC-123".to_string(),
+            "customfield_10100".to_string(),
+            format!(
+                "{CHECKLIST}\n- https://github.com/example-org/widgets/pull/42 [ABC-123] Add widget endpoint"
+            )
+        )));
+    }
+
+    #[tokio::test]
+    async fn routing_rule_enables_dry_run() {
+        let routing = RoutingConfig::from_toml(
+            r#"
+            [[rule]]
+            repository = "example-org/widgets"
+            dry_run = true
+            "#,
+        )
+        .unwrap();
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+
+        handle_routed(
+            &tracker,
+            "pull_request",
+            "pull_request_opened.json",
+            &routing,
+        )
+        .await
+        .unwrap();
+
+        assert!(tracker.updates().is_empty());
+    }
+
+    #[tokio::test]
+    async fn issue_outside_allowed_projects_is_ignored() {
+        let routing = RoutingConfig::from_toml(
+            r#"
+            [[rule]]
+            organization = "example-org"
+            projects = ["XYZ"]
+            "#,
+        )
+        .unwrap();
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+
+        let outcome = handle_routed(
+            &tracker,
+            "pull_request",
+            "pull_request_opened.json",
+            &routing,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(results(&outcome), [("ABC-123", "not_allowed".into())]);
+        assert!(tracker.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn unknown_repository_is_rejected() {
+        let routing = RoutingConfig::from_toml(
+            r#"
+            [[rule]]
+            organization = "other-org"
+            "#,
+        )
+        .unwrap();
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+
+        let error = handle_routed(
+            &tracker,
+            "pull_request",
+            "pull_request_opened.json",
+            &routing,
+        )
+        .await
+        .expect_err("Expected error");
+
+        assert!(matches!(error, Error::UnknownRepository(_)));
+        assert!(tracker.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn missing_issue_is_reported_as_failed() {
+        let tracker = FakeIssueTracker::default();
+
+        let outcome = handle(&tracker, "pull_request", "pull_request_opened.json")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            outcome.issues[0].result,
+            IssueResult::Failed { .. }
+        ));
+        let result = Ok(outcome);
+        assert!(!is_retryable(&result));
+        let response = response(result, None);
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    fn results(outcome: &Outcome) -> Vec<(&str, serde_json::Value)> {
+        outcome
+            .issues
+            .iter()
+            .map(|issue| {
+                let result = serde_json::to_value(&issue.result).unwrap();
+                (issue.issue_key.as_str(), result["result"].clone())
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn outcome_reports_result_per_issue() {
+        let linked = format!("{CHECKLIST}\n- https://github.com/example-org/widgets/pull/42");
+        let cases = [
+            (Some(CHECKLIST), "updated"),
+            (Some(linked.as_str()), "unchanged"),
+            (Some("## Todo\n- write tests"), "missing_section"),
+            (None, "missing_checklist"),
+        ];
+
+        for (checklist, expected) in cases {
+            let tracker = FakeIssueTracker::default().with_issue("ABC-123", checklist);
+
+            let outcome = handle(&tracker, "pull_request", "pull_request_opened.json")
+                .await
+                .unwrap();
+
+            assert_eq!(outcome.issue_keys, ["ABC-123"]);
+            assert!(!outcome.dry_run);
+            assert_eq!(results(&outcome), [("ABC-123", expected.into())]);
         }
-    };
+    }
+
+    #[tokio::test]
+    async fn issue_is_updated_on_site_owning_project() {
+        let routing = RoutingConfig::from_toml(
+            r#"
+            [[rule]]
+
+            [sites.acquired]
+            url = "https://acquired.atlassian.net"
+            email = "bot@acquired.example"
+            token_var = "JIRA_ACQUIRED_TOKEN"
+            projects = ["ABC"]
+            "#,
+        )
+        .unwrap();
+        let default = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+        let acquired = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+        let jira = JiraRegistry::from_iter([
+            (DEFAULT_SITE.to_string(), default.clone()),
+            ("acquired".to_string(), acquired.clone()),
+        ]);
+
+        function_handler(
+            jira,
+            &WebhookSecret::new(SECRET).unwrap().into(),
+            &MemoryDeliveryStore::default(),
+            (&request("pull_request", fixture("pull_request_opened.json"))).into(),
+            &routing,
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert!(default.calls().is_empty());
+        assert_eq!(acquired.updates().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn unknown_site_is_an_error() {
+        let routing = RoutingConfig::from_toml(
+            r#"
+            [[rule]]
+            jira_site = "acquired"
+            "#,
+        )
+        .unwrap();
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+
+        let error = handle_routed(
+            &tracker,
+            "pull_request",
+            "pull_request_opened.json",
+            &routing,
+        )
+        .await
+        .expect_err("Expected error");
+
+        assert!(matches!(error, Error::UnknownJiraSite(site) if site == "acquired"));
+    }
+
+    #[tokio::test]
+    async fn secret_is_selected_by_organization() {
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+        let mut secrets = WebhookSecretMap::new(None);
+        secrets.insert("example-org", WebhookSecret::new(SECRET).unwrap().into());
+
+        function_handler(
+            registry(&tracker),
+            &secrets,
+            &MemoryDeliveryStore::default(),
+            (&request("pull_request", fixture("pull_request_opened.json"))).into(),
+            &RoutingConfig::default(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(tracker.updates().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn unmapped_origin_is_rejected() {
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+        let mut secrets = WebhookSecretMap::new(None);
+        secrets.insert("other-org", WebhookSecret::new(SECRET).unwrap().into());
+
+        let error = function_handler(
+            registry(&tracker),
+            &secrets,
+            &MemoryDeliveryStore::default(),
+            (&request("pull_request", fixture("pull_request_opened.json"))).into(),
+            &RoutingConfig::default(),
+            false,
+        )
+        .await
+        .expect_err("Expected error");
+
+        assert!(matches!(error, Error::NoWebhookSecret(_)));
+        assert!(tracker.calls().is_empty());
+    }
+
+    fn jira_error(status: u16) -> Error {
+        JiraError {
+            status: reqwest::StatusCode::from_u16(status).unwrap(),
+            issue_key: "ABC-123".to_string(),
+            operation: JiraOperation::UpdateChecklist,
+            body: Default::default(),
+        }
+        .into()
+    }
+
+    fn status_and_code(error: Error) -> (u16, String) {
+        let response = response(Err(error), Some("delivery-1"));
+        let body: serde_json::Value = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(body["delivery_id"], "delivery-1");
+        (
+            response.status().as_u16(),
+            body["error"].as_str().unwrap().to_string(),
+        )
+    }
+
+    #[test]
+    fn success_reports_delivery_id() {
+        let response = response(Ok(Outcome::default()), Some("delivery-1"));
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.body(),
+            r#"{"delivery_id":"delivery-1","dry_run":false,"duplicate":false,"issue_keys":[],"issues":[],"queued":false}"#
+        );
+    }
+
+    #[test]
+    fn errors_map_to_status_and_code() {
+        let cases = [
+            (Error::InvalidWebhookSignature, 401, "invalid_signature"),
+            (Error::MissingSignatureHeader, 401, "missing_signature"),
+            (
+                Error::PayloadDeserialization(serde_json::from_str::<u8>("").unwrap_err()),
+                400,
+                "invalid_payload",
+            ),
+            (
+                Error::InvalidEventType("issues".to_string()),
+                202,
+                "ignored_event_type",
+            ),
+            (jira_error(400), 422, "jira_rejected"),
+            (jira_error(403), 422, "jira_rejected"),
+            (jira_error(404), 422, "jira_rejected"),
+            (jira_error(500), 502, "jira_error"),
+            (jira_error(429), 503, "jira_unavailable"),
+            (jira_error(503), 503, "jira_unavailable"),
+            (Error::Internal("boom".to_string()), 500, "internal_error"),
+        ];
+
+        for (error, status, code) in cases {
+            assert_eq!(status_and_code(error), (status, code.to_string()));
+        }
+    }
+
+    #[test]
+    fn config_error_is_a_server_error() {
+        let error = Error::EnvVarNotSet {
+            env_var_name: "WEBHOOK_SECRET".to_string(),
+        };
 
-    LambdaResult::not_ok_response()
+        assert_eq!(
+            status_and_code(error),
+            (500, "configuration_error".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicate_delivery_does_not_touch_jira() {
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+        let deliveries = MemoryDeliveryStore::default();
+        let secrets = WebhookSecret::new(SECRET).unwrap().into();
+        let request = request("pull_request", fixture("pull_request_opened.json"));
+
+        let first = function_handler(
+            registry(&tracker),
+            &secrets,
+            &deliveries,
+            (&request).into(),
+            &RoutingConfig::default(),
+            false,
+        )
+        .await
+        .unwrap();
+        let calls = tracker.calls().len();
+        let second = function_handler(
+            registry(&tracker),
+            &secrets,
+            &deliveries,
+            (&request).into(),
+            &RoutingConfig::default(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert!(!first.duplicate);
+        assert!(second.duplicate);
+        assert!(second.issues.is_empty());
+        assert_eq!(tracker.calls().len(), calls);
+    }
+
+    #[tokio::test]
+    async fn delivery_in_progress_is_retried_later() {
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+        let deliveries = MemoryDeliveryStore::default();
+        let secrets = WebhookSecret::new(SECRET).unwrap().into();
+        let request = request("pull_request", fixture("pull_request_opened.json"));
+        let delivery_id = WebhookRequest::from(&request).delivery_id().unwrap();
+        // the first attempt is still running
+        deliveries.claim(delivery_id).await.unwrap();
+
+        let result = function_handler(
+            registry(&tracker),
+            &secrets,
+            &deliveries,
+            (&request).into(),
+            &RoutingConfig::default(),
+            false,
+        )
+        .await;
+
+        assert!(is_retryable(&result));
+        assert_eq!(
+            response(result, Some(delivery_id)).status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert!(tracker.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_delivery_is_handled_again() {
+        let deliveries = MemoryDeliveryStore::default();
+        let secrets = WebhookSecret::new(SECRET).unwrap().into();
+        let request = request("pull_request", fixture("pull_request_opened.json"));
+
+        let failed = function_handler(
+            registry(&FakeIssueTracker::default()),
+            &secrets,
+            &deliveries,
+            (&request).into(),
+            &RoutingConfig::default(),
+            false,
+        )
+        .await
+        .unwrap();
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+        let retried = function_handler(
+            registry(&tracker),
+            &secrets,
+            &deliveries,
+            (&request).into(),
+            &RoutingConfig::default(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert!(failed.error().is_some());
+        assert!(!retried.duplicate);
+        assert_eq!(tracker.updates().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn older_event_does_not_undo_merge() {
+        use crate::jira::PrStatus;
+
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+        handle(&tracker, "pull_request", "pull_request_merged.json")
+            .await
+            .unwrap();
+        let merged = tracker.checklist("ABC-123").unwrap();
+
+        let outcome = handle(&tracker, "pull_request", "pull_request_opened.json")
+            .await
+            .unwrap();
+
+        assert_eq!(results(&outcome), [("ABC-123", "stale".into())]);
+        assert_eq!(tracker.checklist("ABC-123").unwrap(), merged);
+        assert_eq!(
+            tracker.pull_requests("ABC-123").pull_requests[0].status,
+            PrStatus::Merged
+        );
+    }
+
+    #[derive(Default)]
+    struct RecordingQueue(std::sync::Mutex<Vec<QueuedDelivery>>);
+
+    impl DeliveryQueue for RecordingQueue {
+        fn enqueue<'a>(
+            &'a self,
+            delivery: &'a QueuedDelivery,
+        ) -> crate::secrets::BoxFuture<'a, Result<()>> {
+            self.0.lock().unwrap().push(QueuedDelivery {
+                headers: delivery.headers.clone(),
+                body: delivery.body.clone(),
+            });
+            Box::pin(std::future::ready(Ok(())))
+        }
+    }
+
+    #[tokio::test]
+    async fn verified_delivery_is_enqueued() {
+        let queue = RecordingQueue::default();
+        let request = request("pull_request", fixture("pull_request_opened.json"));
+
+        let outcome = enqueue_handler(
+            &WebhookSecret::new(SECRET).unwrap().into(),
+            &queue,
+            (&request).into(),
+        )
+        .await;
+        let error = enqueue_handler(
+            &WebhookSecret::new("another secret").unwrap().into(),
+            &queue,
+            (&request).into(),
+        )
+        .await
+        .expect_err("Expected error");
+
+        assert_eq!(response(outcome, None).status(), StatusCode::ACCEPTED);
+        assert!(matches!(error, Error::InvalidWebhookSignature));
+        let queued = queue.0.lock().unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(
+            queued[0].to_request().unwrap().body(),
+            request.body().as_bytes()
+        );
+    }
 }
@@ -2,7 +2,8 @@ pub struct ChecklistManipulator {
     checklist: Vec<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PrStatus {
     Open,
     Merged,
@@ -25,20 +26,36 @@ impl ChecklistManipulator {
         Self { checklist }
     }
 
-    pub fn upsert_pr(&mut self, pr_url: &str, status: PrStatus) -> bool {
-        if !self
-            .checklist
+    pub fn has_pr_section(&self) -> bool {
+        self.checklist
             .iter()
             .any(|item| item.ends_with("Pull Requests"))
-        {
+    }
+
+    /// URLs of the pull request entries with the status
+    pub fn pr_urls(&self, status: PrStatus) -> Vec<&str> {
+        self.checklist
+            .iter()
+            .filter_map(|item| item.strip_prefix(status.prefix()))
+            .filter_map(pr_url)
+            .collect()
+    }
+
+    /// The entry is the text after the status prefix, it must contain the url
+    pub fn upsert_pr(&mut self, pr_url: &str, entry: &str, status: PrStatus) -> bool {
+        if !self.has_pr_section() {
             tracing::warn!("Missing pull request section");
             return false;
         }
 
-        let new_entry = format!("{}{pr_url}", status.prefix());
+        let new_entry = format!("{}{entry}", status.prefix());
 
         // Check if PR already exists with any prefix
-        if let Some(pos) = self.checklist.iter().position(|item| item.ends_with(pr_url)) {
+        if let Some(pos) = self
+            .checklist
+            .iter()
+            .position(|item| contains_url(item, pr_url))
+        {
             if self.checklist[pos] == new_entry {
                 tracing::debug!("Pull request already linked with same status");
                 return false;
@@ -51,9 +68,34 @@ impl ChecklistManipulator {
         self.checklist.push(new_entry);
         true
     }
+}
+
+/// The first pull request URL in the entry, whatever the template put around it
+fn pr_url(entry: &str) -> Option<&str> {
+    let pull = entry.find("/pull/")?;
+    let start = entry[..pull].rfind("http")?;
+    let number = &entry[pull + "/pull/".len()..];
+    let digits = number.len()
+        - number
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    (digits > 0).then(|| &entry[start..pull + "/pull/".len() + digits])
+}
+
+/// Whether the url occurs as a whole, e.g. `.../pull/1` in `[.../pull/1|PR]` but not in
+/// `.../pull/12` or `.../pull/1/files`
+fn contains_url(item: &str, url: &str) -> bool {
+    item.match_indices(url).any(|(start, _)| {
+        let before = item[..start].chars().next_back();
+        let after = item[start + url.len()..].chars().next();
+        !before.is_some_and(|c| c.is_alphanumeric() || c == '/')
+            && !after.is_some_and(|c| c.is_ascii_digit() || c == '/')
+    })
+}
 
-    pub fn to_string(&self) -> String {
-        self.checklist.join("\n")
+impl std::fmt::Display for ChecklistManipulator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.checklist.join("\n"))
     }
 }
 
@@ -66,7 +108,11 @@ mod tests {
         let mut checklist =
             ChecklistManipulator::new("## Pull Requests\n- https://github.com/org/repo/pull/1");
 
-        checklist.upsert_pr("https://github.com/org/repo/pull/2", PrStatus::Open);
+        checklist.upsert_pr(
+            "https://github.com/org/repo/pull/2",
+            "https://github.com/org/repo/pull/2",
+            PrStatus::Open,
+        );
 
         assert_eq!(
             &checklist.to_string(),
@@ -78,7 +124,11 @@ mod tests {
     fn test_add_new_pr_merged() {
         let mut checklist = ChecklistManipulator::new("## Pull Requests");
 
-        checklist.upsert_pr("https://github.com/org/repo/pull/1", PrStatus::Merged);
+        checklist.upsert_pr(
+            "https://github.com/org/repo/pull/1",
+            "https://github.com/org/repo/pull/1",
+            PrStatus::Merged,
+        );
 
         assert_eq!(
             &checklist.to_string(),
@@ -90,7 +140,11 @@ mod tests {
     fn test_add_new_pr_closed() {
         let mut checklist = ChecklistManipulator::new("## Pull Requests");
 
-        checklist.upsert_pr("https://github.com/org/repo/pull/1", PrStatus::Closed);
+        checklist.upsert_pr(
+            "https://github.com/org/repo/pull/1",
+            "https://github.com/org/repo/pull/1",
+            PrStatus::Closed,
+        );
 
         assert_eq!(
             &checklist.to_string(),
@@ -98,12 +152,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_upsert_pr_with_delimited_entry() {
+        let mut checklist = ChecklistManipulator::new(
+            "## Pull Requests\n- [https://github.com/org/repo/pull/12|PR]",
+        );
+        let url = "https://github.com/org/repo/pull/1";
+
+        assert!(checklist.upsert_pr(url, &format!("[{url}|PR]"), PrStatus::Open));
+        assert!(!checklist.upsert_pr(url, &format!("[{url}|PR]"), PrStatus::Open));
+        assert!(checklist.upsert_pr(url, &format!("({url})"), PrStatus::Merged));
+
+        assert_eq!(
+            &checklist.to_string(),
+            "## Pull Requests\n- [https://github.com/org/repo/pull/12|PR]\n+ (https://github.com/org/repo/pull/1)"
+        );
+    }
+
     #[test]
     fn test_update_pr_status_open_to_merged() {
         let mut checklist =
             ChecklistManipulator::new("## Pull Requests\n- https://github.com/org/repo/pull/1");
 
-        let updated = checklist.upsert_pr("https://github.com/org/repo/pull/1", PrStatus::Merged);
+        let updated = checklist.upsert_pr(
+            "https://github.com/org/repo/pull/1",
+            "https://github.com/org/repo/pull/1",
+            PrStatus::Merged,
+        );
 
         assert!(updated);
         assert_eq!(
@@ -117,7 +192,11 @@ mod tests {
         let mut checklist =
             ChecklistManipulator::new("## Pull Requests\n- https://github.com/org/repo/pull/1");
 
-        let updated = checklist.upsert_pr("https://github.com/org/repo/pull/1", PrStatus::Closed);
+        let updated = checklist.upsert_pr(
+            "https://github.com/org/repo/pull/1",
+            "https://github.com/org/repo/pull/1",
+            PrStatus::Closed,
+        );
 
         assert!(updated);
         assert_eq!(
@@ -131,8 +210,73 @@ mod tests {
         let mut checklist =
             ChecklistManipulator::new("## Pull Requests\n- https://github.com/org/repo/pull/1");
 
-        let updated = checklist.upsert_pr("https://github.com/org/repo/pull/1", PrStatus::Open);
+        let updated = checklist.upsert_pr(
+            "https://github.com/org/repo/pull/1",
+            "https://github.com/org/repo/pull/1",
+            PrStatus::Open,
+        );
 
         assert!(!updated);
     }
+
+    #[test]
+    fn test_custom_entry_keeps_other_prs() {
+        let mut checklist = ChecklistManipulator::new(
+            "## Pull Requests\n- https://github.com/org/repo/pull/10 (org/repo#10)",
+        );
+
+        let updated = checklist.upsert_pr(
+            "https://github.com/org/repo/pull/1",
+            "https://github.com/org/repo/pull/1 (org/repo#1)",
+            PrStatus::Merged,
+        );
+
+        assert!(updated);
+        assert_eq!(
+            &checklist.to_string(),
+            "## Pull Requests\n- https://github.com/org/repo/pull/10 (org/repo#10)\n+ https://github.com/org/repo/pull/1 (org/repo#1)"
+        );
+    }
+
+    #[test]
+    fn test_custom_entry_updates_status() {
+        let mut checklist = ChecklistManipulator::new(
+            "## Pull Requests\n- https://github.com/org/repo/pull/1 (org/repo#1)",
+        );
+
+        let updated = checklist.upsert_pr(
+            "https://github.com/org/repo/pull/1",
+            "https://github.com/org/repo/pull/1 (org/repo#1)",
+            PrStatus::Closed,
+        );
+
+        assert!(updated);
+        assert_eq!(
+            &checklist.to_string(),
+            "## Pull Requests\nx https://github.com/org/repo/pull/1 (org/repo#1)"
+        );
+    }
+
+    #[test]
+    fn test_pr_urls_by_status() {
+        let checklist = ChecklistManipulator::new(
+            "# Development Process\n- Task 1: Create Pull Request\n# Pull Requests\n\
+             - org/repo#1: https://github.com/org/repo/pull/1\n\
+             + https://github.com/org/repo/pull/2\n\
+             - [https://github.com/org/repo/pull/3|PR]",
+        );
+
+        assert_eq!(
+            checklist.pr_urls(PrStatus::Open),
+            [
+                "https://github.com/org/repo/pull/1",
+                "https://github.com/org/repo/pull/3"
+            ]
+        );
+        assert_eq!(
+            checklist.pr_urls(PrStatus::Merged),
+            ["https://github.com/org/repo/pull/2"]
+        );
+        assert!(checklist.pr_urls(PrStatus::Closed).is_empty());
+    }
 }
@@ -0,0 +1,171 @@
+use crate::error::{Error, JiraError, JiraOperation};
+use crate::jira::property::PullRequestLinks;
+use crate::jira::IssueTracker;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Call {
+    GetChecklist(String, String),
+    UpdateChecklist(String, String, String),
+    GetPullRequests(String),
+    SetPullRequests(String, PullRequestLinks),
+    SearchChecklists(String, String),
+}
+
+/// In-memory issue tracker for tests, clones share their state
+#[derive(Debug, Clone, Default)]
+pub struct FakeIssueTracker {
+    checklists: Arc<Mutex<HashMap<String, Option<String>>>>,
+    failures: Arc<Mutex<HashMap<String, reqwest::StatusCode>>>,
+    pull_requests: Arc<Mutex<HashMap<String, PullRequestLinks>>>,
+    calls: Arc<Mutex<Vec<Call>>>,
+}
+
+impl FakeIssueTracker {
+    pub fn with_issue(self, issue_key: &str, checklist: Option<&str>) -> Self {
+        self.checklists
+            .lock()
+            .unwrap()
+            .insert(issue_key.to_string(), checklist.map(ToString::to_string));
+        self
+    }
+
+    /// Reading the issue fails with the status, e.g. to simulate an outage
+    pub fn with_failure(self, issue_key: &str, status: u16) -> Self {
+        self.failures.lock().unwrap().insert(
+            issue_key.to_string(),
+            reqwest::StatusCode::from_u16(status).unwrap(),
+        );
+        self
+    }
+
+    pub fn checklist(&self, issue_key: &str) -> Option<String> {
+        self.checklists
+            .lock()
+            .unwrap()
+            .get(issue_key)
+            .cloned()
+            .flatten()
+    }
+
+    pub fn pull_requests(&self, issue_key: &str) -> PullRequestLinks {
+        self.pull_requests
+            .lock()
+            .unwrap()
+            .get(issue_key)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    pub fn updates(&self) -> Vec<(String, String)> {
+        self.calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                Call::UpdateChecklist(issue_key, _, checklist) => Some((issue_key, checklist)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn record(&self, call: Call) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+impl IssueTracker for FakeIssueTracker {
+    async fn get_checklist(&self, issue_key: &str, field: &str) -> Result<Option<String>, Error> {
+        self.record(Call::GetChecklist(issue_key.to_string(), field.to_string()));
+
+        if let Some(status) = self.failures.lock().unwrap().get(issue_key) {
+            return Err(failure(issue_key, JiraOperation::GetIssue, *status));
+        }
+        self.checklists
+            .lock()
+            .unwrap()
+            .get(issue_key)
+            .cloned()
+            .ok_or_else(|| not_found(issue_key, JiraOperation::GetIssue))
+    }
+
+    async fn update_checklist(
+        &self,
+        issue_key: &str,
+        field: &str,
+        checklist: String,
+    ) -> Result<(), Error> {
+        self.record(Call::UpdateChecklist(
+            issue_key.to_string(),
+            field.to_string(),
+            checklist.clone(),
+        ));
+
+        match self.checklists.lock().unwrap().get_mut(issue_key) {
+            Some(current) => {
+                *current = Some(checklist);
+                Ok(())
+            }
+            None => Err(not_found(issue_key, JiraOperation::UpdateChecklist)),
+        }
+    }
+
+    async fn get_pull_requests(&self, issue_key: &str) -> Result<PullRequestLinks, Error> {
+        self.record(Call::GetPullRequests(issue_key.to_string()));
+
+        Ok(self.pull_requests(issue_key))
+    }
+
+    async fn set_pull_requests(
+        &self,
+        issue_key: &str,
+        links: &PullRequestLinks,
+    ) -> Result<(), Error> {
+        self.record(Call::SetPullRequests(issue_key.to_string(), links.clone()));
+
+        if !self.checklists.lock().unwrap().contains_key(issue_key) {
+            return Err(not_found(issue_key, JiraOperation::SetProperty));
+        }
+        self.pull_requests
+            .lock()
+            .unwrap()
+            .insert(issue_key.to_string(), links.clone());
+        Ok(())
+    }
+
+    /// Every issue with a checklist, the JQL is only recorded
+    async fn search_checklists(
+        &self,
+        jql: &str,
+        field: &str,
+    ) -> Result<Vec<(String, String)>, Error> {
+        self.record(Call::SearchChecklists(jql.to_string(), field.to_string()));
+
+        let mut issues: Vec<_> = self
+            .checklists
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(key, checklist)| Some((key.clone(), checklist.clone()?)))
+            .collect();
+        issues.sort();
+        Ok(issues)
+    }
+}
+
+fn not_found(issue_key: &str, operation: JiraOperation) -> Error {
+    failure(issue_key, operation, reqwest::StatusCode::NOT_FOUND)
+}
+
+fn failure(issue_key: &str, operation: JiraOperation, status: reqwest::StatusCode) -> Error {
+    JiraError {
+        status,
+        issue_key: issue_key.to_string(),
+        operation,
+        body: Default::default(),
+    }
+    .into()
+}
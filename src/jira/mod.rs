@@ -1,6 +1,14 @@
 pub mod checklist;
 pub mod client;
+#[cfg(test)]
+pub mod fake;
 pub mod models;
+pub mod oauth;
+pub mod property;
+pub mod registry;
+pub mod tracker;
 
 pub use checklist::*;
 pub use client::*;
+pub use registry::*;
+pub use tracker::*;
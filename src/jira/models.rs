@@ -1,15 +1,37 @@
+use crate::jira::oauth::OAuthCredentials;
+use crate::types::Secret;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct JiraIssue {
     pub key: String,
-    pub fields: JiraFields,
+    pub fields: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct JiraFields {
-    #[serde(rename = "customfield_10369")]
-    pub checklist: ContentNode,
+impl JiraIssue {
+    /// Text of the checklist field, `None` if it is missing or empty
+    pub fn checklist(&self, field: &str) -> Option<String> {
+        let value = self.fields.get(field)?;
+        ChecklistField::deserialize(value).ok()?.text()
+    }
+}
+
+/// Jira Cloud returns the checklist as ADF, Data Center as plain wiki markup text
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ChecklistField {
+    Adf(ContentNode),
+    Text(String),
+}
+
+impl ChecklistField {
+    pub fn text(&self) -> Option<String> {
+        match self {
+            Self::Adf(node) => node.text(),
+            Self::Text(text) if text.is_empty() => None,
+            Self::Text(text) => Some(text.clone()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -44,9 +66,7 @@ impl ContentNode {
             Self::Text { text } => Some(text.clone()),
             Self::HardBreak => Some("\n".to_string()),
             Self::Doc { content, .. } | Self::Paragraph { content } => {
-                let parts: Vec<String> = content.iter()
-                    .filter_map(|node| node.text())
-                    .collect();
+                let parts: Vec<String> = content.iter().filter_map(|node| node.text()).collect();
                 if parts.is_empty() {
                     None
                 } else {
@@ -57,16 +77,109 @@ impl ContentNode {
     }
 }
 
+/// Error response of the Jira REST API
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JiraErrorBody {
+    #[serde(default)]
+    pub error_messages: Vec<String>,
+    #[serde(default)]
+    pub errors: std::collections::BTreeMap<String, String>,
+}
+
+impl JiraErrorBody {
+    /// Falls back to the raw text for responses that are not Jira error JSON, e.g. from a proxy
+    pub fn parse(body: &str) -> Self {
+        serde_json::from_str(body).unwrap_or_else(|_| Self {
+            error_messages: (!body.is_empty())
+                .then(|| body.to_string())
+                .into_iter()
+                .collect(),
+            errors: Default::default(),
+        })
+    }
+}
+
+impl std::fmt::Display for JiraErrorBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let messages: Vec<String> = self
+            .error_messages
+            .iter()
+            .cloned()
+            .chain(
+                self.errors
+                    .iter()
+                    .map(|(field, message)| format!("{field}: {message}")),
+            )
+            .collect();
+
+        if messages.is_empty() {
+            f.write_str("no error details")
+        } else {
+            f.write_str(&messages.join("; "))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JiraFlavor {
+    #[default]
+    Cloud,
+    DataCenter,
+}
+
+impl JiraFlavor {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(flavor: &str) -> Option<Self> {
+        match flavor.to_lowercase().as_str() {
+            "cloud" => Some(Self::Cloud),
+            "datacenter" | "data-center" | "server" => Some(Self::DataCenter),
+            _ => None,
+        }
+    }
+
+    /// Relative path of the REST API, must be joined onto the base url
+    pub fn api_path(&self) -> &'static str {
+        match self {
+            Self::Cloud => "rest/api/3/",
+            Self::DataCenter => "rest/api/2/",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum JiraAuth {
+    /// Jira Cloud: account email and API token
+    Basic { email: String, api_token: Secret },
+    /// Jira Data Center: personal access token
+    PersonalAccessToken { token: Secret },
+    /// Jira Cloud: OAuth 2.0 client credentials of a service account
+    OAuth(OAuthCredentials),
+}
+
 #[derive(Debug, Clone)]
 pub struct JiraConfig {
-    pub email: String,
-    pub api_token: String,
+    pub flavor: JiraFlavor,
+    pub auth: JiraAuth,
     pub base_url: reqwest::Url,
 }
 
+/// The user the client is authenticated as
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JiraUser {
+    pub display_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JiraField {
+    pub id: String,
+    pub name: String,
+}
+
 #[cfg(test)]
 mod test {
-    use super::JiraIssue;
+    use super::{JiraErrorBody, JiraFlavor, JiraIssue};
 
     fn sample_issue() -> JiraIssue {
         let json = r##"{"expand":"renderedFields,names,schema,operations,editmeta,changelog,versionedRepresentations","id":"1337","self":"https://example.atlassian.net/rest/api/3/issue/1337","key":"TEST-7","fields":{"customfield_10369":{"type":"doc","version":1,"content":[{"type":"paragraph","content":[{"type":"text","text":"# Development Process\n-! Task 1: Detail Planning including Risk Assessment\n-! Task 2: Document Risk Assessment\n-! Task 3: Create Feature Branch\n-! Task 4: Development including Tests and Documentation\n-! Task 5: Create Pull Request and Request Review\n-! Task 6: Request Functional Acceptance\n-! Task 7: Prepare Deployment\n-! Task 8: Request Deployment/Merge Approval\n-! Task 9: Merge Pull Request\n-! Task 10: Execute Deployment\n-! Task 11: Verify Production Delivery\n# Pull Requests\n"}]}]}}}"##;
@@ -77,4 +190,54 @@ mod test {
     fn deserializes() {
         sample_issue();
     }
+
+    #[test]
+    fn missing_checklist_field_is_empty() {
+        let issue = sample_issue();
+
+        assert!(issue.checklist("customfield_10369").is_some());
+        assert_eq!(issue.checklist("customfield_10100"), None);
+    }
+
+    #[test]
+    fn deserializes_data_center_text_field() {
+        let json = r##"{"id":"1337","key":"TEST-7","fields":{"customfield_10369":"# Pull Requests\n- https://github.com/org/repo/pull/1"}}"##;
+        let issue: JiraIssue = serde_json::from_str(json).expect("failed to deserialize");
+
+        assert_eq!(
+            issue.checklist("customfield_10369"),
+            Some("# Pull Requests\n- https://github.com/org/repo/pull/1".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_flavor() {
+        assert_eq!(JiraFlavor::from_str("cloud"), Some(JiraFlavor::Cloud));
+        assert_eq!(JiraFlavor::from_str("Server"), Some(JiraFlavor::DataCenter));
+        assert_eq!(
+            JiraFlavor::from_str("datacenter"),
+            Some(JiraFlavor::DataCenter)
+        );
+        assert_eq!(JiraFlavor::from_str("onprem"), None);
+    }
+
+    #[test]
+    fn parses_error_body() {
+        let body = JiraErrorBody::parse(
+            r#"{"errorMessages":[],"errors":{"customfield_10369":"Field 'customfield_10369' cannot be set. It is not on the appropriate screen, or unknown."}}"#,
+        );
+
+        assert_eq!(
+            body.to_string(),
+            "customfield_10369: Field 'customfield_10369' cannot be set. It is not on the appropriate screen, or unknown."
+        );
+    }
+
+    #[test]
+    fn parses_non_json_error_body() {
+        let body = JiraErrorBody::parse("<html>Bad Gateway</html>");
+
+        assert_eq!(body.error_messages, vec!["<html>Bad Gateway</html>"]);
+        assert_eq!(JiraErrorBody::parse("").to_string(), "no error details");
+    }
 }
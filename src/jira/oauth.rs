@@ -0,0 +1,247 @@
+use crate::error::Error;
+use crate::types::Secret;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+pub const DEFAULT_TOKEN_URL: &str = "https://auth.atlassian.com/oauth/token";
+
+/// Tokens are refreshed this long before they actually expire
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct OAuthCredentials {
+    pub client_id: String,
+    pub client_secret: Secret,
+    pub token_url: reqwest::Url,
+}
+
+#[derive(Debug, Serialize)]
+struct TokenRequest<'a> {
+    grant_type: &'static str,
+    client_id: &'a str,
+    client_secret: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// Error response of the token endpoint as defined by RFC 6749
+#[derive(Debug, Default, Deserialize)]
+struct TokenErrorBody {
+    #[serde(default)]
+    error: String,
+    error_description: Option<String>,
+}
+
+impl TokenErrorBody {
+    /// Falls back to the raw text for responses that are not OAuth error JSON, e.g. from a proxy
+    fn parse(body: &str) -> Self {
+        serde_json::from_str(body).unwrap_or_else(|_| Self {
+            error: body.to_string(),
+            error_description: None,
+        })
+    }
+}
+
+impl std::fmt::Display for TokenErrorBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.error.as_str(), &self.error_description) {
+            ("", None) => f.write_str("no error details"),
+            ("", Some(description)) => f.write_str(description),
+            (error, None) => f.write_str(error),
+            (error, Some(description)) => write!(f, "{error}: {description}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct AccessToken {
+    token: Secret,
+    expires_at: Instant,
+}
+
+/// Shared between clones of the client, so a token is only fetched once per Lambda instance
+#[derive(Debug, Clone, Default)]
+pub struct TokenCache(Arc<Mutex<Option<AccessToken>>>);
+
+impl TokenCache {
+    pub async fn access_token(
+        &self,
+        client: &reqwest::Client,
+        credentials: &OAuthCredentials,
+    ) -> Result<Secret, Error> {
+        let mut cached = self.0.lock().await;
+
+        if let Some(token) = cached.as_ref() {
+            if Instant::now() + EXPIRY_MARGIN < token.expires_at {
+                return Ok(token.token.clone());
+            }
+            tracing::debug!("Access token expired");
+        }
+
+        let token = fetch_access_token(client, credentials).await?;
+        let access_token = token.token.clone();
+        *cached = Some(token);
+
+        Ok(access_token)
+    }
+}
+
+async fn fetch_access_token(
+    client: &reqwest::Client,
+    credentials: &OAuthCredentials,
+) -> Result<AccessToken, Error> {
+    tracing::debug!("Fetching access token");
+
+    let requested_at = Instant::now();
+
+    let response = client
+        .post(credentials.token_url.clone())
+        .json(&TokenRequest {
+            grant_type: "client_credentials",
+            client_id: &credentials.client_id,
+            client_secret: credentials.client_secret.expose(),
+        })
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = TokenErrorBody::parse(&response.text().await.unwrap_or_default());
+
+        tracing::error!(
+            status = status.as_u16(),
+            error = body.error,
+            error_description = body.error_description,
+            "Fetching access token failed"
+        );
+        return Err(Error::JiraApi(format!(
+            "Failed to fetch access token: {status}: {body}"
+        )));
+    }
+
+    let token: TokenResponse = response
+        .json()
+        .await
+        .map_err(|e| Error::JiraApi(format!("Failed to parse access token: {}", e)))?;
+
+    Ok(AccessToken {
+        token: Secret::new(token.access_token),
+        expires_at: requested_at + Duration::from_secs(token.expires_in),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn credentials(server: &MockServer) -> OAuthCredentials {
+        OAuthCredentials {
+            client_id: "client".to_string(),
+            client_secret: "secret".into(),
+            token_url: reqwest::Url::parse(&format!("{}/oauth/token", server.uri())).unwrap(),
+        }
+    }
+
+    fn token_response(expires_in: u64) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": "access-token",
+            "expires_in": expires_in,
+            "token_type": "Bearer",
+            "scope": "read:jira-work write:jira-work"
+        }))
+    }
+
+    #[tokio::test]
+    async fn caches_token_until_expiry() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .and(body_json(serde_json::json!({
+                "grant_type": "client_credentials",
+                "client_id": "client",
+                "client_secret": "secret"
+            })))
+            .respond_with(token_response(3600))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let cache = TokenCache::default();
+
+        for _ in 0..3 {
+            let token = cache
+                .access_token(&client, &credentials(&server))
+                .await
+                .unwrap();
+            assert_eq!(token.expose(), "access-token");
+        }
+    }
+
+    #[tokio::test]
+    async fn refreshes_expired_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .respond_with(token_response(EXPIRY_MARGIN.as_secs()))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let cache = TokenCache::default();
+
+        cache
+            .access_token(&client, &credentials(&server))
+            .await
+            .unwrap();
+        cache
+            .access_token(&client, &credentials(&server))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn reports_token_endpoint_failure() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+                "error": "access_denied",
+                "error_description": "Unauthorized"
+            })))
+            .mount(&server)
+            .await;
+
+        let error = TokenCache::default()
+            .access_token(&reqwest::Client::new(), &credentials(&server))
+            .await
+            .expect_err("Expected error");
+
+        assert_eq!(
+            error.to_string(),
+            "Jira API error: Failed to fetch access token: 401 Unauthorized: access_denied: Unauthorized"
+        );
+    }
+
+    #[test]
+    fn token_error_body_falls_back_to_text() {
+        assert_eq!(
+            TokenErrorBody::parse(r#"{"error":"invalid_client"}"#).to_string(),
+            "invalid_client"
+        );
+        assert_eq!(
+            TokenErrorBody::parse("Bad Gateway").to_string(),
+            "Bad Gateway"
+        );
+        assert_eq!(TokenErrorBody::parse("").to_string(), "no error details");
+    }
+}
@@ -0,0 +1,132 @@
+use crate::jira::PrStatus;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Issue entity property holding the pull requests linked to an issue
+pub const PULL_REQUESTS_PROPERTY: &str = "github.pullRequests";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestLinks {
+    #[serde(default)]
+    pub pull_requests: Vec<PullRequestLink>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestLink {
+    pub repository: String,
+    pub number: u64,
+    pub url: String,
+    pub status: PrStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closed_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merged_at: Option<DateTime<Utc>>,
+}
+
+impl PullRequestLinks {
+    pub fn find(&self, repository: &str, number: u64) -> Option<&PullRequestLink> {
+        self.pull_requests
+            .iter()
+            .find(|link| link.repository == repository && link.number == number)
+    }
+
+    /// Whether an event older than the one already applied produced the link, a merged pull
+    /// request stays merged unless it was reopened
+    pub fn is_stale(&self, link: &PullRequestLink, reopened: bool) -> bool {
+        let Some(applied) = self.find(&link.repository, link.number) else {
+            return false;
+        };
+        if link.updated_at < applied.updated_at {
+            return true;
+        }
+        applied.status == PrStatus::Merged && link.status != PrStatus::Merged && !reopened
+    }
+
+    /// Returns whether the links changed
+    pub fn upsert(&mut self, link: PullRequestLink) -> bool {
+        if self.find(&link.repository, link.number) == Some(&link) {
+            return false;
+        }
+
+        match self.pull_requests.iter_mut().find(|existing| {
+            existing.repository == link.repository && existing.number == link.number
+        }) {
+            Some(existing) => *existing = link,
+            None => self.pull_requests.push(link),
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(number: u64, status: PrStatus) -> PullRequestLink {
+        PullRequestLink {
+            repository: "org/repo".to_string(),
+            number,
+            url: format!("https://github.com/org/repo/pull/{number}"),
+            status,
+            created_at: "2026-10-18T10:00:00Z".parse().unwrap(),
+            updated_at: "2026-10-18T10:00:00Z".parse().unwrap(),
+            closed_at: None,
+            merged_at: None,
+        }
+    }
+
+    #[test]
+    fn upsert_adds_and_updates_links() {
+        let mut links = PullRequestLinks::default();
+
+        assert!(links.upsert(link(1, PrStatus::Open)));
+        assert!(links.upsert(link(2, PrStatus::Open)));
+        assert!(!links.upsert(link(1, PrStatus::Open)));
+        assert!(links.upsert(link(1, PrStatus::Merged)));
+
+        assert_eq!(links.pull_requests.len(), 2);
+        assert_eq!(links.find("org/repo", 1).unwrap().status, PrStatus::Merged);
+    }
+
+    #[test]
+    fn older_and_unmerging_links_are_stale() {
+        let mut links = PullRequestLinks::default();
+        links.upsert(link(1, PrStatus::Merged));
+
+        let mut older = link(1, PrStatus::Open);
+        older.updated_at = "2026-10-18T09:00:00Z".parse().unwrap();
+        let mut newer = link(1, PrStatus::Open);
+        newer.updated_at = "2026-10-18T11:00:00Z".parse().unwrap();
+
+        assert!(links.is_stale(&older, true));
+        assert!(links.is_stale(&newer, false));
+        assert!(!links.is_stale(&newer, true));
+        assert!(!links.is_stale(&link(1, PrStatus::Merged), false));
+        assert!(!links.is_stale(&link(2, PrStatus::Open), false));
+    }
+
+    #[test]
+    fn serializes_for_jql() {
+        let links = PullRequestLinks {
+            pull_requests: vec![link(1, PrStatus::Open)],
+        };
+
+        assert_eq!(
+            serde_json::to_value(&links).unwrap(),
+            serde_json::json!({
+                "pullRequests": [{
+                    "repository": "org/repo",
+                    "number": 1,
+                    "url": "https://github.com/org/repo/pull/1",
+                    "status": "open",
+                    "createdAt": "2026-10-18T10:00:00Z",
+                    "updatedAt": "2026-10-18T10:00:00Z"
+                }]
+            })
+        );
+    }
+}
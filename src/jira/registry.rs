@@ -0,0 +1,34 @@
+use crate::error::Error;
+use crate::jira::{IssueTracker, JiraClient};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Issue trackers of all configured Jira sites by name
+#[derive(Debug, Clone)]
+pub struct JiraRegistry<T = JiraClient> {
+    sites: Arc<BTreeMap<String, T>>,
+}
+
+impl<T: IssueTracker> JiraRegistry<T> {
+    pub fn new(sites: BTreeMap<String, T>) -> Self {
+        Self {
+            sites: Arc::new(sites),
+        }
+    }
+
+    pub fn site(&self, name: &str) -> Result<&T, Error> {
+        self.sites
+            .get(name)
+            .ok_or_else(|| Error::UnknownJiraSite(name.to_string()))
+    }
+
+    pub fn sites(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.sites.iter().map(|(name, site)| (name.as_str(), site))
+    }
+}
+
+impl<T: IssueTracker> FromIterator<(String, T)> for JiraRegistry<T> {
+    fn from_iter<I: IntoIterator<Item = (String, T)>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
@@ -0,0 +1,83 @@
+use crate::error::Error;
+use crate::jira::property::PullRequestLinks;
+use crate::jira::JiraClient;
+use std::future::Future;
+
+/// Operations the event handler needs from an issue tracker
+pub trait IssueTracker: Clone + Send + Sync + 'static {
+    /// Text of the checklist field, `None` if the field is empty
+    fn get_checklist(
+        &self,
+        issue_key: &str,
+        field: &str,
+    ) -> impl Future<Output = Result<Option<String>, Error>> + Send;
+
+    fn update_checklist(
+        &self,
+        issue_key: &str,
+        field: &str,
+        checklist: String,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Pull requests linked to the issue, empty if none were recorded yet
+    fn get_pull_requests(
+        &self,
+        issue_key: &str,
+    ) -> impl Future<Output = Result<PullRequestLinks, Error>> + Send;
+
+    fn set_pull_requests(
+        &self,
+        issue_key: &str,
+        links: &PullRequestLinks,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Keys and checklist text of the issues matching the JQL, issues without checklist are left out
+    fn search_checklists(
+        &self,
+        jql: &str,
+        field: &str,
+    ) -> impl Future<Output = Result<Vec<(String, String)>, Error>> + Send;
+}
+
+impl IssueTracker for JiraClient {
+    async fn get_checklist(&self, issue_key: &str, field: &str) -> Result<Option<String>, Error> {
+        let issue = self.get_issue(issue_key, field).await?;
+        Ok(issue.checklist(field))
+    }
+
+    async fn update_checklist(
+        &self,
+        issue_key: &str,
+        field: &str,
+        checklist: String,
+    ) -> Result<(), Error> {
+        JiraClient::update_checklist(self, issue_key, field, checklist).await
+    }
+
+    async fn get_pull_requests(&self, issue_key: &str) -> Result<PullRequestLinks, Error> {
+        JiraClient::get_pull_requests(self, issue_key).await
+    }
+
+    async fn set_pull_requests(
+        &self,
+        issue_key: &str,
+        links: &PullRequestLinks,
+    ) -> Result<(), Error> {
+        JiraClient::set_pull_requests(self, issue_key, links).await
+    }
+
+    async fn search_checklists(
+        &self,
+        jql: &str,
+        field: &str,
+    ) -> Result<Vec<(String, String)>, Error> {
+        let issues = self.search(jql, field).await?;
+        Ok(issues
+            .into_iter()
+            .filter_map(|issue| {
+                let checklist = issue.checklist(field)?;
+                Some((issue.key, checklist))
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,50 @@
+use crate::error::Result;
+use crate::http_handler;
+use crate::outcome::Outcome;
+use crate::queue::QueueMessage;
+use aws_lambda_events::sqs::{BatchItemFailure, SqsBatchResponse, SqsEvent};
+use lambda_http::{Body, Response};
+
+pub type LambdaResult = std::result::Result<Response<Body>, LambdaError>;
+
+pub type LambdaError = lambda_http::Error;
+
+pub fn result_to_http_reponse(
+    response: Result<Outcome>,
+    delivery_id: Option<&str>,
+) -> LambdaResult {
+    Ok(http_handler::response(response, delivery_id).map(Body::Text))
+}
+
+/// Messages of the SQS batch, a message is on its last attempt once SQS received it
+/// `max_receives` times, or always if that is unknown
+pub fn sqs_messages(
+    event: SqsEvent,
+    max_receives: Option<u32>,
+) -> impl Iterator<Item = QueueMessage> {
+    event.records.into_iter().filter_map(move |message| {
+        let receive_count = message
+            .attributes
+            .get("ApproximateReceiveCount")
+            .and_then(|count| count.parse::<u32>().ok());
+        let last_attempt = match (receive_count, max_receives) {
+            (Some(receive_count), Some(max_receives)) => receive_count >= max_receives,
+            _ => true,
+        };
+        Some(QueueMessage {
+            id: message.message_id?,
+            body: message.body.unwrap_or_default(),
+            last_attempt,
+        })
+    })
+}
+
+/// Reports the failed messages so that only those are redriven
+pub fn sqs_batch_response(failed: Vec<String>) -> SqsBatchResponse {
+    SqsBatchResponse {
+        batch_item_failures: failed
+            .into_iter()
+            .map(|item_identifier| BatchItemFailure { item_identifier })
+            .collect(),
+    }
+}
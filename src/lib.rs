@@ -0,0 +1,25 @@
+pub mod archive;
+pub mod backfill;
+pub mod cli;
+pub mod config;
+pub mod deliveries;
+pub mod doctor;
+pub mod error;
+pub mod event_handler;
+pub mod github;
+pub mod http_handler;
+pub mod jira;
+#[cfg(feature = "lambda")]
+pub mod lambda;
+pub mod metrics;
+pub mod outcome;
+pub mod queue;
+pub mod reconcile;
+pub mod redelivery;
+pub mod routing;
+pub mod secrets;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod simulate;
+pub mod state;
+pub mod types;
@@ -1,31 +1,64 @@
+use github_webhook::archive::{self, DeliveryArchive};
+use github_webhook::deliveries::{self, DeliveryStore};
+use github_webhook::http_handler::{self, WebhookRequest};
+use github_webhook::queue::{self, DeliveryQueue};
+use github_webhook::{cli, lambda, secrets, state};
 use lambda_http::{run, service_fn, tracing};
-
-mod config;
-mod error;
-mod event_handler;
-mod github;
-mod http_handler;
-mod jira;
-mod types;
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> Result<(), lambda_http::Error> {
     tracing::init_default_subscriber();
-    let aws_config = aws_config::load_defaults(aws_config::BehaviorVersion::v2025_01_17()).await;
-    let aws_kms = aws_sdk_kms::Client::new(&aws_config);
 
-    let config = config::Config::from_env(&aws_kms).await?;
+    let secrets = secrets::SecretStore::from_env().await?;
+
+    if let Some(code) = cli::run(&secrets, std::env::args().skip(1)).await {
+        std::process::exit(code);
+    }
 
-    let jira_client = jira::JiraClient::new(config.jira_config);
+    let deliveries: Arc<dyn DeliveryStore> = deliveries::from_env().await?.into();
+    let archive: Option<Arc<dyn DeliveryArchive>> = archive::from_env().await?.map(Arc::from);
+    let queue: Option<Arc<dyn DeliveryQueue>> = queue::from_env().await?.map(Arc::from);
+
+    let state = match state::SharedState::load(secrets).await {
+        Ok(state) => Arc::new(state),
+        Err(error) => {
+            tracing::error!("{error}");
+            return Err(error.into());
+        }
+    };
 
-    run(service_fn(move |event| {
-        let client = jira_client.clone();
-        let webhook_secret = config.webhook_secret.clone();
+    run(service_fn(move |event: lambda_http::Request| {
+        let state = state.clone();
+        let deliveries = deliveries.clone();
+        let archive = archive.clone();
+        let queue = queue.clone();
 
         async move {
-            http_handler::result_to_http_reponse(
-                http_handler::function_handler(client, webhook_secret, event, config.dry_run).await,
-            )
+            let state = state.current().await;
+            let request = WebhookRequest::from(&event);
+            let result = match queue {
+                Some(queue) => {
+                    http_handler::enqueue_handler(&state.webhook_secrets, queue.as_ref(), request)
+                        .await
+                }
+                None => {
+                    let result = http_handler::function_handler(
+                        state.jira.clone(),
+                        &state.webhook_secrets,
+                        deliveries.as_ref(),
+                        request,
+                        &state.routing,
+                        state.dry_run,
+                    )
+                    .await;
+                    if let Some(archive) = &archive {
+                        archive::archive_failure(archive.as_ref(), request, &result).await;
+                    }
+                    result
+                }
+            };
+            lambda::result_to_http_reponse(result, request.delivery_id())
         }
     }))
     .await
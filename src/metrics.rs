@@ -0,0 +1,58 @@
+//! Metrics in CloudWatch embedded metric format, extracted by CloudWatch from the function logs
+
+use chrono::Utc;
+use serde_json::{json, Value};
+
+const NAMESPACE: &str = "GitHubWebhook";
+
+/// Counts one occurrence of `name`
+pub fn count(name: &str, dimensions: &[(&str, &str)]) {
+    println!(
+        "{}",
+        embedded_metric(name, 1.0, dimensions, Utc::now().timestamp_millis())
+    );
+}
+
+fn embedded_metric(name: &str, value: f64, dimensions: &[(&str, &str)], timestamp: i64) -> Value {
+    let mut metric = json!({
+        "_aws": {
+            "Timestamp": timestamp,
+            "CloudWatchMetrics": [{
+                "Namespace": NAMESPACE,
+                "Dimensions": [dimensions.iter().map(|(key, _)| key).collect::<Vec<_>>()],
+                "Metrics": [{ "Name": name, "Unit": "Count" }],
+            }],
+        },
+        name: value,
+    });
+    for (key, value) in dimensions {
+        metric[key] = json!(value);
+    }
+    metric
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_embedded_metric() {
+        let metric = embedded_metric("Deliveries", 1.0, &[("Repository", "org/repo")], 1_000);
+
+        assert_eq!(
+            metric,
+            json!({
+                "_aws": {
+                    "Timestamp": 1_000,
+                    "CloudWatchMetrics": [{
+                        "Namespace": "GitHubWebhook",
+                        "Dimensions": [["Repository"]],
+                        "Metrics": [{ "Name": "Deliveries", "Unit": "Count" }],
+                    }],
+                },
+                "Deliveries": 1.0,
+                "Repository": "org/repo",
+            })
+        );
+    }
+}
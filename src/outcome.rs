@@ -0,0 +1,140 @@
+//! What a delivery did to the linked issues, returned as the response body
+
+use crate::error::Error;
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, Default, Serialize)]
+pub struct Outcome {
+    /// Issue keys extracted from the pull request title
+    pub issue_keys: Vec<String>,
+    pub dry_run: bool,
+    /// The delivery was already handled and is acknowledged without touching Jira
+    pub duplicate: bool,
+    /// The delivery was verified and enqueued for the worker
+    pub queued: bool,
+    pub issues: Vec<IssueOutcome>,
+}
+
+impl Outcome {
+    /// The first issue update that failed in a way that may succeed when retried, else the first
+    /// one that failed, so the delivery is retried if any issue needs it
+    pub fn error(&self) -> Option<&Error> {
+        let mut errors = self.issues.iter().filter_map(|issue| match &issue.result {
+            IssueResult::Failed { error } => Some(error),
+            _ => None,
+        });
+        let first = errors.next()?;
+        let retryable = |error: &&Error| crate::http_handler::classify(error).0.is_server_error();
+        Some(
+            std::iter::once(first)
+                .chain(errors)
+                .find(retryable)
+                .unwrap_or(first),
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct IssueOutcome {
+    pub issue_key: String,
+    #[serde(flatten)]
+    pub result: IssueResult,
+    /// Whether the `github.pullRequests` property changed
+    pub property_updated: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum IssueResult {
+    Updated,
+    Unchanged,
+    MissingChecklist,
+    MissingSection,
+    /// The issue is in a project the routing rule does not allow
+    NotAllowed,
+    /// A newer event of the pull request was already applied
+    Stale,
+    Failed {
+        #[serde(serialize_with = "error_message")]
+        error: Error,
+    },
+}
+
+fn error_message<S: Serializer>(error: &Error, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_result_per_issue() {
+        let outcome = Outcome {
+            issue_keys: vec!["ABC-1".to_string(), "ABC-2".to_string()],
+            dry_run: false,
+            duplicate: false,
+            queued: false,
+            issues: vec![
+                IssueOutcome {
+                    issue_key: "ABC-1".to_string(),
+                    result: IssueResult::MissingSection,
+                    property_updated: true,
+                },
+                IssueOutcome {
+                    issue_key: "ABC-2".to_string(),
+                    result: IssueResult::Failed {
+                        error: Error::JiraApi("Jira is down".to_string()),
+                    },
+                    property_updated: false,
+                },
+            ],
+        };
+
+        assert_eq!(
+            serde_json::to_value(&outcome).unwrap(),
+            serde_json::json!({
+                "issue_keys": ["ABC-1", "ABC-2"],
+                "dry_run": false,
+                "duplicate": false,
+                "queued": false,
+                "issues": [
+                    { "issue_key": "ABC-1", "result": "missing_section", "property_updated": true },
+                    {
+                        "issue_key": "ABC-2",
+                        "result": "failed",
+                        "error": "Jira API error: Jira is down",
+                        "property_updated": false
+                    }
+                ]
+            })
+        );
+        assert!(outcome.error().is_some());
+    }
+
+    #[test]
+    fn error_prefers_retryable_failure() {
+        use crate::error::{JiraError, JiraOperation};
+
+        let failed = |issue_key: &str, error: Error| IssueOutcome {
+            issue_key: issue_key.to_string(),
+            result: IssueResult::Failed { error },
+            property_updated: false,
+        };
+        let not_found = JiraError {
+            status: reqwest::StatusCode::NOT_FOUND,
+            issue_key: "ABC-1".to_string(),
+            operation: JiraOperation::GetIssue,
+            body: Default::default(),
+        };
+        let outcome = Outcome {
+            issues: vec![
+                failed("ABC-1", not_found.into()),
+                failed("ABC-2", Error::JiraApi("Jira is down".to_string())),
+            ],
+            ..Outcome::default()
+        };
+
+        assert!(matches!(outcome.error(), Some(Error::JiraApi(_))));
+    }
+}
@@ -0,0 +1,317 @@
+//! Asynchronous mode: the webhook enqueues verified deliveries and the worker handles them
+
+use crate::archive::{self, DeliveryArchive};
+use crate::deliveries::DeliveryStore;
+use crate::error::Error;
+use crate::http_handler::{self, WebhookRequest};
+use crate::jira::{IssueTracker, JiraRegistry};
+use crate::routing::RoutingConfig;
+use crate::secrets::BoxFuture;
+use crate::types::WebhookSecretMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[cfg(feature = "aws-sqs")]
+mod sqs;
+
+#[cfg(feature = "aws-sqs")]
+pub use sqs::SqsDeliveryQueue;
+
+const DELIVERY_QUEUE_URL: &str = "DELIVERY_QUEUE_URL";
+const DELIVERY_MAX_RECEIVES: &str = "DELIVERY_MAX_RECEIVES";
+
+/// A message of a batch the worker received
+#[derive(Debug)]
+pub struct QueueMessage {
+    pub id: String,
+    pub body: String,
+    /// The queue gives up on the message if it fails again, a retryable failure is archived then
+    pub last_attempt: bool,
+}
+
+/// `maxReceiveCount` of the queue's redrive policy from `DELIVERY_MAX_RECEIVES`, every attempt
+/// counts as the last one if unset
+pub fn max_receives_from_env() -> Result<Option<u32>, Error> {
+    std::env::var(DELIVERY_MAX_RECEIVES)
+        .ok()
+        .map(|max_receives| {
+            max_receives.parse().map_err(|_| Error::EnvVarBadValue {
+                env_var_name: DELIVERY_MAX_RECEIVES.to_string(),
+            })
+        })
+        .transpose()
+}
+
+/// Headers and body of a delivery as received by the webhook
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct QueuedDelivery {
+    pub headers: BTreeMap<String, String>,
+    /// Base64 encoded, the signature is verified again on the exact bytes
+    pub body: String,
+}
+
+impl QueuedDelivery {
+    pub fn new(request: WebhookRequest<'_>) -> Self {
+        use base64::{engine::general_purpose::STANDARD as Base64, Engine as _};
+
+        Self {
+            headers: request
+                .headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect(),
+            body: Base64.encode(request.body),
+        }
+    }
+
+    pub fn to_request(&self) -> Result<http::Request<Vec<u8>>, Error> {
+        use base64::{engine::general_purpose::STANDARD as Base64, Engine as _};
+
+        let body = Base64
+            .decode(&self.body)
+            .map_err(|e| Error::Internal(format!("queued body is not base64: {e}")))?;
+        let mut request = http::Request::builder().method("POST");
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        request
+            .body(body)
+            .map_err(|e| Error::Internal(format!("queued headers are invalid: {e}")))
+    }
+}
+
+/// Queue the webhook hands deliveries to
+pub trait DeliveryQueue: Send + Sync {
+    fn enqueue<'a>(&'a self, delivery: &'a QueuedDelivery) -> BoxFuture<'a, Result<(), Error>>;
+}
+
+/// SQS queue `DELIVERY_QUEUE_URL` if set, deliveries are handled synchronously otherwise
+pub async fn from_env() -> Result<Option<Box<dyn DeliveryQueue>>, Error> {
+    match std::env::var(DELIVERY_QUEUE_URL) {
+        Err(_) => Ok(None),
+        #[cfg(feature = "aws-sqs")]
+        Ok(queue_url) => {
+            let aws_config =
+                aws_config::load_defaults(aws_config::BehaviorVersion::v2026_01_12()).await;
+            Ok(Some(Box::new(SqsDeliveryQueue::from_env(
+                &aws_config,
+                queue_url,
+            ))))
+        }
+        #[cfg(not(feature = "aws-sqs"))]
+        Ok(_) => Err(Error::EnvVarBadValue {
+            env_var_name: DELIVERY_QUEUE_URL.to_string(),
+        }),
+    }
+}
+
+/// Handles a batch of messages in order, returns the IDs of the messages that failed with a
+/// retryable error and are left to the queue's redrive policy, on their last attempt they are
+/// archived as well
+#[allow(clippy::too_many_arguments)]
+pub async fn process_batch<T: IssueTracker>(
+    jira: JiraRegistry<T>,
+    webhook_secrets: &WebhookSecretMap,
+    deliveries: &dyn DeliveryStore,
+    archive: Option<&dyn DeliveryArchive>,
+    routing: &RoutingConfig,
+    dry_run: bool,
+    messages: impl IntoIterator<Item = QueueMessage>,
+) -> Vec<String> {
+    let mut failed = Vec::new();
+    for QueueMessage {
+        id: message_id,
+        body,
+        last_attempt,
+    } in messages
+    {
+        let request = match serde_json::from_str::<QueuedDelivery>(&body)
+            .map_err(Error::from)
+            .and_then(|delivery| delivery.to_request())
+        {
+            Ok(request) => request,
+            Err(error) => {
+                // retrying doesn't help, the message is dropped
+                tracing::error!(message_id, ?error, "Malformed queued delivery");
+                continue;
+            }
+        };
+
+        let result = http_handler::function_handler(
+            jira.clone(),
+            webhook_secrets,
+            deliveries,
+            (&request).into(),
+            routing,
+            dry_run,
+        )
+        .await;
+
+        http_handler::log_failure(&result);
+        // permanent failures like an unknown issue are acknowledged, redriving them can't help
+        if !http_handler::is_retryable(&result) {
+            continue;
+        }
+        if let Some(archive) = archive.filter(|_| last_attempt) {
+            archive::archive_failure(archive, (&request).into(), &result).await;
+        }
+        failed.push(message_id);
+    }
+    failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::FilesystemArchive;
+    use crate::deliveries::MemoryDeliveryStore;
+    use crate::github::{signed_request, TEST_SECRET as SECRET};
+    use crate::jira::fake::FakeIssueTracker;
+    use crate::routing::DEFAULT_SITE;
+    use crate::types::WebhookSecret;
+
+    fn message(message_id: &str, body: &str) -> QueueMessage {
+        let request = signed_request("pull_request", message_id, body.to_string(), SECRET);
+        let delivery = QueuedDelivery::new((&request).into());
+        QueueMessage {
+            id: message_id.to_string(),
+            body: serde_json::to_string(&delivery).unwrap(),
+            last_attempt: false,
+        }
+    }
+
+    fn fixture(name: &str) -> String {
+        std::fs::read_to_string(format!(
+            "{}/tests/fixtures/{name}",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap()
+    }
+
+    /// Pull request linking `XYZ-1`
+    fn linking_xyz() -> String {
+        let mut payload: serde_json::Value =
+            serde_json::from_str(&fixture("pull_request_opened.json")).unwrap();
+        payload["pull_request"]["title"] = "[XYZ-1] Change".into();
+        payload.to_string()
+    }
+
+    fn jira_down() -> FakeIssueTracker {
+        FakeIssueTracker::default().with_failure("XYZ-1", 503)
+    }
+
+    async fn process(
+        tracker: &FakeIssueTracker,
+        archive: Option<&dyn DeliveryArchive>,
+        messages: Vec<QueueMessage>,
+    ) -> Vec<String> {
+        process_batch(
+            JiraRegistry::from_iter([(DEFAULT_SITE.to_string(), tracker.clone())]),
+            &WebhookSecret::new(SECRET).unwrap().into(),
+            &MemoryDeliveryStore::default(),
+            archive,
+            &RoutingConfig::default(),
+            false,
+            messages,
+        )
+        .await
+    }
+
+    #[test]
+    fn round_trips_headers_and_body() {
+        let request = http::Request::builder()
+            .header("X-GitHub-Event", "ping")
+            .body(vec![0, 159, 146, 150])
+            .unwrap();
+
+        let delivery = QueuedDelivery::new((&request).into());
+        let restored = delivery.to_request().unwrap();
+
+        assert_eq!(restored.headers()["x-github-event"], "ping");
+        assert_eq!(restored.body(), request.body());
+    }
+
+    #[tokio::test]
+    async fn reports_only_retryable_failures() {
+        let tracker = jira_down().with_issue(
+            "ABC-123",
+            Some("## Pull Requests\n- https://github.com/example-org/widgets/pull/1"),
+        );
+
+        let failed = process(
+            &tracker,
+            None,
+            vec![
+                message("handled", &fixture("pull_request_opened.json")),
+                message("jira-fails", &linking_xyz()),
+                message("malformed-payload", "{}"),
+                QueueMessage {
+                    id: "not-a-delivery".to_string(),
+                    body: "garbage".to_string(),
+                    last_attempt: true,
+                },
+            ],
+        )
+        .await;
+
+        assert_eq!(failed, ["jira-fails"]);
+        assert_eq!(tracker.updates().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn archives_failures_on_last_attempt() {
+        let dir = std::env::temp_dir().join(format!(
+            "github-webhook-queue-archive-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let archive = FilesystemArchive::new(&dir);
+
+        let failed = process(
+            &jira_down(),
+            Some(&archive),
+            vec![
+                message("retried", &linking_xyz()),
+                QueueMessage {
+                    last_attempt: true,
+                    ..message("given-up", &linking_xyz())
+                },
+            ],
+        )
+        .await;
+
+        assert_eq!(failed, ["retried", "given-up"]);
+        let archived = archive.list().await.unwrap();
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].delivery_id.as_deref(), Some("given-up"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn acknowledges_unknown_issue() {
+        let dir = std::env::temp_dir().join(format!(
+            "github-webhook-queue-unknown-issue-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let archive = FilesystemArchive::new(&dir);
+        let tracker = FakeIssueTracker::default();
+
+        let failed = process(
+            &tracker,
+            Some(&archive),
+            vec![QueueMessage {
+                last_attempt: true,
+                ..message("unknown-issue", &linking_xyz())
+            }],
+        )
+        .await;
+
+        assert!(failed.is_empty(), "{failed:?}");
+        assert!(!tracker.calls().is_empty());
+        assert!(archive.list().await.unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,117 @@
+use super::{DeliveryQueue, QueuedDelivery};
+use crate::error::Error;
+use crate::secrets::BoxFuture;
+
+const SQS_ENDPOINT_URL: &str = "SQS_ENDPOINT_URL";
+/// Largest message body SQS accepts
+const MAX_MESSAGE_SIZE: usize = 256 * 1024;
+
+pub struct SqsDeliveryQueue {
+    client: aws_sdk_sqs::Client,
+    queue_url: String,
+}
+
+impl SqsDeliveryQueue {
+    pub fn new(client: aws_sdk_sqs::Client, queue_url: String) -> Self {
+        Self { client, queue_url }
+    }
+
+    /// The endpoint can be overridden with `SQS_ENDPOINT_URL`, e.g. for ElasticMQ or LocalStack
+    pub fn from_env(aws_config: &aws_config::SdkConfig, queue_url: String) -> Self {
+        let mut config = aws_sdk_sqs::config::Builder::from(aws_config);
+        if let Ok(url) = std::env::var(SQS_ENDPOINT_URL) {
+            config = config.endpoint_url(url);
+        }
+        Self::new(aws_sdk_sqs::Client::from_conf(config.build()), queue_url)
+    }
+}
+
+impl DeliveryQueue for SqsDeliveryQueue {
+    fn enqueue<'a>(&'a self, delivery: &'a QueuedDelivery) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            // SQS would reject it as well, but with an error that looks retryable
+            let body = serde_json::to_string(delivery)?;
+            if body.len() > MAX_MESSAGE_SIZE {
+                return Err(Error::PayloadTooLarge {
+                    size: body.len(),
+                    limit: MAX_MESSAGE_SIZE,
+                });
+            }
+            self.client
+                .send_message()
+                .queue_url(&self.queue_url)
+                .message_body(body)
+                .send()
+                .await
+                .map_err(|e| Error::Sqs(Box::new(e.into())))?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_handler::classify;
+    use crate::secrets::test_sdk_config as sdk_config;
+    use wiremock::matchers::{body_partial_json, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn sends_delivery_as_message() {
+        let server = MockServer::start().await;
+        let delivery = QueuedDelivery {
+            headers: [("x-github-event".to_string(), "ping".to_string())].into(),
+            body: "e30=".to_string(),
+        };
+        Mock::given(method("POST"))
+            .and(header("x-amz-target", "AmazonSQS.SendMessage"))
+            .and(body_partial_json(serde_json::json!({
+                "QueueUrl": "https://sqs.eu-central-1.amazonaws.com/123456789012/deliveries",
+                "MessageBody": serde_json::to_string(&delivery).unwrap()
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                r#"{"MessageId":"5fea7756-0ea4-451a-a703-a558b933e274"}"#,
+                "application/x-amz-json-1.0",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let queue = SqsDeliveryQueue::new(
+            aws_sdk_sqs::Client::new(&sdk_config(&server)),
+            "https://sqs.eu-central-1.amazonaws.com/123456789012/deliveries".to_string(),
+        );
+
+        queue.enqueue(&delivery).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_delivery_over_message_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let delivery = QueuedDelivery {
+            headers: [("x-github-event".to_string(), "push".to_string())].into(),
+            body: "A".repeat(MAX_MESSAGE_SIZE),
+        };
+
+        let queue = SqsDeliveryQueue::new(
+            aws_sdk_sqs::Client::new(&sdk_config(&server)),
+            "https://sqs.eu-central-1.amazonaws.com/123456789012/deliveries".to_string(),
+        );
+
+        let error = queue.enqueue(&delivery).await.unwrap_err();
+        assert!(
+            matches!(error, Error::PayloadTooLarge { limit, .. } if limit == MAX_MESSAGE_SIZE),
+            "{error:?}"
+        );
+        assert_eq!(
+            classify(&error),
+            (http::StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large")
+        );
+    }
+}
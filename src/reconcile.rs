@@ -0,0 +1,337 @@
+//! Corrects checklist entries of pull requests that were merged or closed while their events were
+//! missed, by asking GitHub for the real state of every pull request still listed as open
+
+use crate::event_handler::{pr_status, update_issue};
+use crate::github::client::GitHubClient;
+use crate::github::models::{PullRequestPayload, Repository};
+use crate::jira::{ChecklistManipulator, IssueTracker, JiraRegistry, PrStatus};
+use crate::outcome::IssueResult;
+use crate::routing::{RoutingConfig, Rule};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt;
+
+const RECONCILE_JQL: &str = "RECONCILE_JQL";
+const DEFAULT_JQL: &str = "{field} is not EMPTY AND updated >= -30d";
+
+/// JQL selecting the issues to look at from `RECONCILE_JQL`, `{field}` stands for the checklist
+/// field
+pub fn jql_from_env() -> String {
+    std::env::var(RECONCILE_JQL).unwrap_or_else(|_| DEFAULT_JQL.to_string())
+}
+
+/// JQL clause name of the field, custom fields are referred to by id
+fn jql_field(field: &str) -> String {
+    match field.strip_prefix("customfield_") {
+        Some(id) => format!("cf[{id}]"),
+        None => field.to_string(),
+    }
+}
+
+/// Repository full name and number of a pull request URL like
+/// `https://github.com/example-org/widgets/pull/42`
+fn parse_pr_url(url: &str) -> Option<(String, u64)> {
+    let path = url.split_once("://")?.1;
+    let mut segments = path.split('/').skip(1);
+    let owner = segments.next()?;
+    let repository = segments.next()?;
+    if segments.next()? != "pull" {
+        return None;
+    }
+    let number = segments.next()?.parse().ok()?;
+    Some((format!("{owner}/{repository}"), number))
+}
+
+/// An open entry whose pull request is merged or closed
+#[derive(Debug, Serialize)]
+pub struct Correction {
+    pub issue_key: String,
+    pub url: String,
+    pub status: PrStatus,
+    pub dry_run: bool,
+    #[serde(flatten)]
+    pub result: IssueResult,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ReconcileReport {
+    /// Open entries looked up on GitHub
+    pub checked: usize,
+    pub corrections: Vec<Correction>,
+    /// Searches that failed
+    pub errors: Vec<String>,
+}
+
+impl ReconcileReport {
+    pub fn passed(&self) -> bool {
+        self.errors.is_empty()
+            && !self
+                .corrections
+                .iter()
+                .any(|correction| matches!(correction.result, IssueResult::Failed { .. }))
+    }
+}
+
+impl fmt::Display for ReconcileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for correction in &self.corrections {
+            let Correction {
+                issue_key,
+                url,
+                status,
+                ..
+            } = correction;
+            match &correction.result {
+                IssueResult::Updated if correction.dry_run => {
+                    writeln!(f, "WOULD FIX {issue_key} {url} -> {status:?}")?
+                }
+                IssueResult::Updated => writeln!(f, "FIXED {issue_key} {url} -> {status:?}")?,
+                IssueResult::Failed { error } => writeln!(f, "FAIL {issue_key} {url}: {error}")?,
+                result => writeln!(f, "SKIP {issue_key} {url}: {result:?}")?,
+            }
+        }
+        for error in &self.errors {
+            writeln!(f, "FAIL {error}")?;
+        }
+        writeln!(
+            f,
+            "{} open pull requests checked, {} not open anymore",
+            self.checked,
+            self.corrections.len()
+        )
+    }
+}
+
+/// Searches every site for issues with a checklist in one of the routed checklist fields and
+/// updates the open entries whose pull request is merged or closed
+pub async fn reconcile<T: IssueTracker>(
+    jira: &JiraRegistry<T>,
+    github: &GitHubClient,
+    routing: &RoutingConfig,
+    jql: &str,
+    dry_run: bool,
+) -> ReconcileReport {
+    let fields: BTreeSet<&str> = routing
+        .rules
+        .iter()
+        .map(|rule| rule.checklist_field.as_str())
+        .collect();
+
+    let mut report = ReconcileReport::default();
+    for (site, issue_tracker) in jira.sites() {
+        for field in &fields {
+            let query = jql.replace("{field}", &jql_field(field));
+            let issues = match issue_tracker.search_checklists(&query, field).await {
+                Ok(issues) => issues,
+                Err(error) => {
+                    report.errors.push(format!("{site} {field}: {error}"));
+                    continue;
+                }
+            };
+
+            for (issue_key, checklist) in issues {
+                for url in ChecklistManipulator::new(&checklist).pr_urls(PrStatus::Open) {
+                    let Some((repository, number)) = parse_pr_url(url) else {
+                        continue;
+                    };
+                    // other sites and fields are searched on their own
+                    let Ok(rule) = routing.route(&repository) else {
+                        continue;
+                    };
+                    if rule.checklist_field != *field || routing.site_for(rule, &issue_key) != site
+                    {
+                        continue;
+                    }
+
+                    report.checked += 1;
+                    let Some(correction) = reconcile_entry(
+                        issue_tracker,
+                        github,
+                        &issue_key,
+                        url,
+                        (&repository, number),
+                        rule,
+                        rule.dry_run(dry_run),
+                    )
+                    .await
+                    else {
+                        continue;
+                    };
+                    report.corrections.push(correction);
+                }
+            }
+        }
+    }
+    report
+}
+
+async fn reconcile_entry<T: IssueTracker>(
+    issue_tracker: &T,
+    github: &GitHubClient,
+    issue_key: &str,
+    url: &str,
+    (repository, number): (&str, u64),
+    rule: &Rule,
+    dry_run: bool,
+) -> Option<Correction> {
+    let correction = |status, result| Correction {
+        issue_key: issue_key.to_string(),
+        url: url.to_string(),
+        status,
+        dry_run,
+        result,
+    };
+
+    let pull_request = match github.pull_request(repository, number).await {
+        Ok(pull_request) => pull_request,
+        Err(error) => return Some(correction(PrStatus::Open, IssueResult::Failed { error })),
+    };
+    let status = pr_status(&pull_request);
+    if status == PrStatus::Open {
+        return None;
+    }
+
+    tracing::info!(issue_key, url, ?status, "Pull request is not open anymore");
+    // the canonical name as the webhook would have reported it
+    let full_name = parse_pr_url(&pull_request.html_url)
+        .map_or_else(|| repository.to_string(), |(full_name, _)| full_name);
+    let payload = PullRequestPayload {
+        action: "closed".to_string(),
+        pull_request,
+        changes: None,
+        repository: Repository { full_name },
+    };
+    let result = match update_issue(issue_tracker, issue_key, &payload, rule, status, dry_run).await
+    {
+        Ok(outcome) => outcome.result,
+        Err(error) => IssueResult::Failed { error },
+    };
+    Some(correction(status, result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jira::fake::FakeIssueTracker;
+    use crate::routing::DEFAULT_SITE;
+    use crate::types::Secret;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const CHECKLIST: &str =
+        "# Development Process\n-! Task 1: Create Pull Request\n# Pull Requests\n\
+                             - https://github.com/example-org/widgets/pull/1\n\
+                             - https://github.com/example-org/widgets/pull/2";
+
+    async fn mock_pull_request(server: &MockServer, number: u64, state: &str, merged: bool) {
+        Mock::given(method("GET"))
+            .and(path(format!("/repos/example-org/widgets/pulls/{number}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "title": format!("[ABC-123] Change {number}"),
+                "html_url": format!("https://github.com/example-org/widgets/pull/{number}"),
+                "number": number,
+                "state": state,
+                "merged": merged,
+                "created_at": "2026-10-18T10:00:00Z",
+                "updated_at": "2026-10-19T10:00:00Z",
+                "closed_at": merged.then_some("2026-10-19T10:00:00Z"),
+                "merged_at": merged.then_some("2026-10-19T10:00:00Z")
+            })))
+            .mount(server)
+            .await;
+    }
+
+    fn github(server: &MockServer) -> GitHubClient {
+        GitHubClient::new(
+            reqwest::Url::parse(&format!("{}/", server.uri())).unwrap(),
+            &Secret::from("token".to_string()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_pull_request_urls() {
+        assert_eq!(
+            parse_pr_url("https://github.com/example-org/widgets/pull/42"),
+            Some(("example-org/widgets".to_string(), 42))
+        );
+        assert_eq!(
+            parse_pr_url("https://github.com/example-org/widgets/issues/42"),
+            None
+        );
+        assert_eq!(jql_field("customfield_10369"), "cf[10369]");
+    }
+
+    #[tokio::test]
+    async fn merged_pull_requests_are_corrected() {
+        let server = MockServer::start().await;
+        mock_pull_request(&server, 1, "closed", true).await;
+        mock_pull_request(&server, 2, "open", false).await;
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+        let jira = JiraRegistry::from_iter([(DEFAULT_SITE.to_string(), tracker.clone())]);
+
+        let report = reconcile(
+            &jira,
+            &github(&server),
+            &RoutingConfig::default(),
+            DEFAULT_JQL,
+            false,
+        )
+        .await;
+
+        assert!(report.passed());
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.corrections.len(), 1);
+        assert_eq!(report.corrections[0].status, PrStatus::Merged);
+        assert!(matches!(report.corrections[0].result, IssueResult::Updated));
+        assert_eq!(
+            tracker.checklist("ABC-123").unwrap(),
+            CHECKLIST.replace(
+                "- https://github.com/example-org/widgets/pull/1",
+                "+ https://github.com/example-org/widgets/pull/1"
+            )
+        );
+        assert_eq!(
+            tracker.pull_requests("ABC-123").pull_requests[0].status,
+            PrStatus::Merged
+        );
+        assert!(tracker
+            .calls()
+            .contains(&crate::jira::fake::Call::SearchChecklists(
+                "cf[10369] is not EMPTY AND updated >= -30d".to_string(),
+                "customfield_10369".to_string()
+            )));
+    }
+
+    #[tokio::test]
+    async fn dry_run_only_reports() {
+        let server = MockServer::start().await;
+        mock_pull_request(&server, 1, "closed", false).await;
+        Mock::given(method("GET"))
+            .and(path("/repos/example-org/widgets/pulls/2"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        let tracker = FakeIssueTracker::default().with_issue("ABC-123", Some(CHECKLIST));
+        let jira = JiraRegistry::from_iter([(DEFAULT_SITE.to_string(), tracker.clone())]);
+
+        let report = reconcile(
+            &jira,
+            &github(&server),
+            &RoutingConfig::default(),
+            DEFAULT_JQL,
+            true,
+        )
+        .await;
+
+        assert!(!report.passed());
+        assert_eq!(report.corrections.len(), 2);
+        assert_eq!(report.corrections[0].status, PrStatus::Closed);
+        assert!(report.corrections[0].dry_run);
+        assert!(matches!(
+            report.corrections[1].result,
+            IssueResult::Failed { .. }
+        ));
+        assert!(tracker.updates().is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+use super::CursorStore;
+use crate::error::Error;
+use crate::secrets::BoxFuture;
+use aws_sdk_dynamodb::types::AttributeValue;
+
+const DYNAMODB_ENDPOINT_URL: &str = "DYNAMODB_ENDPOINT_URL";
+
+/// Cursors in a table with the string partition key `name` and the number attribute `cursor`
+pub struct DynamoDbCursorStore {
+    client: aws_sdk_dynamodb::Client,
+    table: String,
+}
+
+impl DynamoDbCursorStore {
+    pub fn new(client: aws_sdk_dynamodb::Client, table: String) -> Self {
+        Self { client, table }
+    }
+
+    /// The endpoint can be overridden with `DYNAMODB_ENDPOINT_URL`, e.g. for DynamoDB local
+    pub fn from_env(aws_config: &aws_config::SdkConfig, table: String) -> Self {
+        let mut config = aws_sdk_dynamodb::config::Builder::from(aws_config);
+        if let Ok(url) = std::env::var(DYNAMODB_ENDPOINT_URL) {
+            config = config.endpoint_url(url);
+        }
+        Self::new(aws_sdk_dynamodb::Client::from_conf(config.build()), table)
+    }
+}
+
+impl CursorStore for DynamoDbCursorStore {
+    fn load<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Option<u64>, Error>> {
+        Box::pin(async move {
+            let output = self
+                .client
+                .get_item()
+                .table_name(&self.table)
+                .key("name", AttributeValue::S(name.to_string()))
+                .consistent_read(true)
+                .send()
+                .await
+                .map_err(|e| Error::DynamoDb(Box::new(e.into())))?;
+
+            match output.item().and_then(|item| item.get("cursor")) {
+                None => Ok(None),
+                Some(AttributeValue::N(cursor)) => cursor
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| Error::Internal(format!("invalid cursor {cursor} of {name}"))),
+                Some(_) => Err(Error::Internal(format!("cursor of {name} is no number"))),
+            }
+        })
+    }
+
+    fn save<'a>(&'a self, name: &'a str, cursor: u64) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            self.client
+                .put_item()
+                .table_name(&self.table)
+                .item("name", AttributeValue::S(name.to_string()))
+                .item("cursor", AttributeValue::N(cursor.to_string()))
+                .send()
+                .await
+                .map_err(|e| Error::DynamoDb(Box::new(e.into())))?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::test_sdk_config as sdk_config;
+    use wiremock::matchers::{body_partial_json, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn loads_saved_cursor() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("x-amz-target", "DynamoDB_20120810.GetItem"))
+            .and(body_partial_json(serde_json::json!({
+                "TableName": "cursors",
+                "Key": { "name": { "S": "example-org/widgets:1" } }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                r#"{"Item":{"name":{"S":"example-org/widgets:1"},"cursor":{"N":"42"}}}"#,
+                "application/x-amz-json-1.0",
+            ))
+            .mount(&server)
+            .await;
+
+        let store = DynamoDbCursorStore::new(
+            aws_sdk_dynamodb::Client::new(&sdk_config(&server)),
+            "cursors".to_string(),
+        );
+        assert_eq!(store.load("example-org/widgets:1").await.unwrap(), Some(42));
+    }
+}
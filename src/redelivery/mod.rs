@@ -0,0 +1,342 @@
+//! Redelivers failed deliveries found through GitHub's hook deliveries API, run on a schedule to
+//! catch deliveries that failed while the webhook was down
+
+use crate::error::Error;
+use crate::github::client::{GitHubClient, Hook};
+use crate::github::models::HookDelivery;
+use crate::secrets::BoxFuture;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Mutex;
+
+#[cfg(feature = "aws-dynamodb")]
+mod dynamodb;
+
+#[cfg(feature = "aws-dynamodb")]
+pub use dynamodb::DynamoDbCursorStore;
+
+const GITHUB_HOOKS: &str = "GITHUB_HOOKS";
+const CURSOR_TABLE: &str = "CURSOR_TABLE";
+
+/// GitHub only redelivers deliveries of the past three days
+const REDELIVERY_WINDOW: Duration = Duration::days(3);
+
+/// Remembers the newest delivery handled per hook
+pub trait CursorStore: Send + Sync {
+    fn load<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Option<u64>, Error>>;
+
+    fn save<'a>(&'a self, name: &'a str, cursor: u64) -> BoxFuture<'a, Result<(), Error>>;
+}
+
+/// Cursors of this process only, each cold start looks at the whole redelivery window again
+#[derive(Default)]
+pub struct MemoryCursorStore(Mutex<HashMap<String, u64>>);
+
+impl CursorStore for MemoryCursorStore {
+    fn load<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Option<u64>, Error>> {
+        let cursor = self.0.lock().unwrap().get(name).copied();
+        Box::pin(std::future::ready(Ok(cursor)))
+    }
+
+    fn save<'a>(&'a self, name: &'a str, cursor: u64) -> BoxFuture<'a, Result<(), Error>> {
+        self.0.lock().unwrap().insert(name.to_string(), cursor);
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+/// DynamoDB table `CURSOR_TABLE` if set, else in memory
+pub async fn cursor_store_from_env() -> Result<Box<dyn CursorStore>, Error> {
+    match std::env::var(CURSOR_TABLE) {
+        Err(_) => Ok(Box::new(MemoryCursorStore::default())),
+        #[cfg(feature = "aws-dynamodb")]
+        Ok(table) => {
+            let aws_config =
+                aws_config::load_defaults(aws_config::BehaviorVersion::v2026_01_12()).await;
+            Ok(Box::new(DynamoDbCursorStore::from_env(&aws_config, table)))
+        }
+        #[cfg(not(feature = "aws-dynamodb"))]
+        Ok(_) => Err(Error::EnvVarBadValue {
+            env_var_name: CURSOR_TABLE.to_string(),
+        }),
+    }
+}
+
+/// Comma separated hooks in `GITHUB_HOOKS`, e.g. `example-org/widgets:12345,example-org:678`
+pub fn hooks_from_env() -> Result<Vec<Hook>, Error> {
+    let hooks = std::env::var(GITHUB_HOOKS).map_err(|_| Error::EnvVarNotSet {
+        env_var_name: GITHUB_HOOKS.to_string(),
+    })?;
+    hooks
+        .split(',')
+        .filter(|hook| !hook.trim().is_empty())
+        .map(|hook| {
+            hook.parse().map_err(|problem| {
+                tracing::error!("{problem}");
+                Error::EnvVarBadValue {
+                    env_var_name: GITHUB_HOOKS.to_string(),
+                }
+            })
+        })
+        .collect()
+}
+
+/// A failed delivery and whether redelivering it worked
+#[derive(Debug, Serialize)]
+pub struct Redelivery {
+    pub hook: String,
+    pub delivery_id: u64,
+    pub guid: String,
+    pub event: String,
+    pub status_code: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Redeliveries of a run plus hooks that could not be checked
+#[derive(Debug, Default, Serialize)]
+pub struct RedeliveryReport {
+    pub redeliveries: Vec<Redelivery>,
+    pub errors: Vec<String>,
+}
+
+impl RedeliveryReport {
+    pub fn passed(&self) -> bool {
+        self.errors.is_empty()
+            && self
+                .redeliveries
+                .iter()
+                .all(|redelivery| redelivery.error.is_none())
+    }
+}
+
+impl fmt::Display for RedeliveryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for redelivery in &self.redeliveries {
+            let Redelivery {
+                hook,
+                delivery_id,
+                guid,
+                event,
+                status_code,
+                ..
+            } = redelivery;
+            match &redelivery.error {
+                None => writeln!(
+                    f,
+                    "REDELIVERED {hook} {delivery_id} ({event} {guid}, status {status_code})"
+                )?,
+                Some(error) => writeln!(f, "FAIL {hook} {delivery_id} ({event} {guid}): {error}")?,
+            }
+        }
+        for error in &self.errors {
+            writeln!(f, "FAIL {error}")?;
+        }
+        writeln!(f, "{} deliveries redelivered", self.redeliveries.len())
+    }
+}
+
+/// Redelivers the failed deliveries of every hook that arrived after its cursor, unless the
+/// delivery succeeded or was redelivered in the meantime
+pub async fn redeliver_failed(
+    github: &GitHubClient,
+    hooks: &[Hook],
+    cursors: &dyn CursorStore,
+    now: DateTime<Utc>,
+) -> RedeliveryReport {
+    let mut report = RedeliveryReport::default();
+    for hook in hooks {
+        if let Err(error) = redeliver_hook(github, hook, cursors, now, &mut report).await {
+            report.errors.push(format!("{hook}: {error}"));
+        }
+    }
+    report
+}
+
+async fn redeliver_hook(
+    github: &GitHubClient,
+    hook: &Hook,
+    cursors: &dyn CursorStore,
+    now: DateTime<Utc>,
+    report: &mut RedeliveryReport,
+) -> Result<(), Error> {
+    let name = hook.to_string();
+    let cursor = cursors.load(&name).await?.unwrap_or(0);
+    let oldest = now - REDELIVERY_WINDOW;
+
+    let mut deliveries = Vec::new();
+    let mut next = None;
+    'pages: loop {
+        let page = github.hook_deliveries(hook, next).await?;
+        for delivery in page.items {
+            if delivery.id <= cursor || delivery.delivered_at < oldest {
+                break 'pages;
+            }
+            deliveries.push(delivery);
+        }
+        match page.next {
+            Some(url) => next = Some(url),
+            None => break,
+        }
+    }
+
+    let Some(newest) = deliveries.iter().map(|delivery| delivery.id).max() else {
+        return Ok(());
+    };
+
+    let mut new_cursor = newest;
+    for delivery in to_redeliver(&deliveries) {
+        let result = github.redeliver(hook, delivery.id).await;
+        if result.is_err() {
+            // picked up again on the next run
+            new_cursor = new_cursor.min(delivery.id - 1);
+        }
+        report.redeliveries.push(Redelivery {
+            hook: name.clone(),
+            delivery_id: delivery.id,
+            guid: delivery.guid.clone(),
+            event: delivery.event.clone(),
+            status_code: delivery.status_code,
+            error: result.err().map(|error| error.to_string()),
+        });
+    }
+
+    cursors.save(&name, new_cursor).await
+}
+
+/// The newest failed delivery of every GUID that neither succeeded nor was redelivered, a failed
+/// redelivery is not redelivered again
+fn to_redeliver(deliveries: &[HookDelivery]) -> Vec<&HookDelivery> {
+    let settled: HashSet<&str> = deliveries
+        .iter()
+        .filter(|delivery| delivery.redelivery || !delivery.failed())
+        .map(|delivery| delivery.guid.as_str())
+        .collect();
+
+    let mut seen = HashSet::new();
+    deliveries
+        .iter()
+        .filter(|delivery| !settled.contains(delivery.guid.as_str()))
+        .filter(|delivery| seen.insert(delivery.guid.as_str()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Secret;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn delivery(id: u64, guid: &str, status_code: u16, redelivery: bool) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "guid": guid,
+            "delivered_at": "2026-10-18T10:00:00Z",
+            "redelivery": redelivery,
+            "status": if status_code == 200 { "OK" } else { "Invalid HTTP Response: 503" },
+            "status_code": status_code,
+            "event": "pull_request",
+            "action": "closed"
+        })
+    }
+
+    async fn github(server: &MockServer, deliveries: Vec<serde_json::Value>) -> GitHubClient {
+        Mock::given(method("GET"))
+            .and(path("/repos/example-org/widgets/hooks/1/deliveries"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(deliveries))
+            .mount(server)
+            .await;
+        GitHubClient::new(
+            reqwest::Url::parse(&format!("{}/", server.uri())).unwrap(),
+            &Secret::from("token".to_string()),
+        )
+        .unwrap()
+    }
+
+    fn now() -> DateTime<Utc> {
+        "2026-10-19T10:00:00Z".parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn redelivers_unsettled_failures_once() {
+        let server = MockServer::start().await;
+        let github = github(
+            &server,
+            vec![
+                delivery(6, "guid-a", 200, true),
+                delivery(5, "guid-c", 503, false),
+                delivery(4, "guid-b", 503, true),
+                delivery(3, "guid-c", 503, false),
+                delivery(2, "guid-b", 503, false),
+                delivery(1, "guid-a", 0, false),
+            ],
+        )
+        .await;
+        Mock::given(method("POST"))
+            .and(path(
+                "/repos/example-org/widgets/hooks/1/deliveries/5/attempts",
+            ))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let hooks = vec!["example-org/widgets:1".parse().unwrap()];
+        let cursors = MemoryCursorStore::default();
+        let report = redeliver_failed(&github, &hooks, &cursors, now()).await;
+
+        assert!(report.passed());
+        assert_eq!(report.redeliveries.len(), 1);
+        assert_eq!(report.redeliveries[0].guid, "guid-c");
+        assert_eq!(
+            cursors.load("example-org/widgets:1").await.unwrap(),
+            Some(6)
+        );
+
+        // nothing after the cursor
+        let report = redeliver_failed(&github, &hooks, &cursors, now()).await;
+        assert!(report.redeliveries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_redelivery_is_retried_next_run() {
+        let server = MockServer::start().await;
+        let github = github(
+            &server,
+            vec![
+                delivery(8, "guid-e", 200, false),
+                delivery(7, "guid-d", 503, false),
+            ],
+        )
+        .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let hooks = vec!["example-org/widgets:1".parse().unwrap()];
+        let cursors = MemoryCursorStore::default();
+        let report = redeliver_failed(&github, &hooks, &cursors, now()).await;
+
+        assert!(!report.passed());
+        assert_eq!(
+            cursors.load("example-org/widgets:1").await.unwrap(),
+            Some(6)
+        );
+    }
+
+    #[tokio::test]
+    async fn ignores_deliveries_outside_the_window() {
+        let server = MockServer::start().await;
+        let github = github(&server, vec![delivery(9, "guid-f", 503, false)]).await;
+
+        let hooks = vec!["example-org/widgets:1".parse().unwrap()];
+        let cursors = MemoryCursorStore::default();
+        let report = redeliver_failed(&github, &hooks, &cursors, now() + Duration::days(4)).await;
+
+        assert!(report.redeliveries.is_empty());
+        assert_eq!(cursors.load("example-org/widgets:1").await.unwrap(), None);
+    }
+}
@@ -0,0 +1,343 @@
+use crate::error::Error;
+use crate::github::models::PullRequestPayload;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+pub const DEFAULT_SITE: &str = "default";
+pub const DEFAULT_CHECKLIST_FIELD: &str = "customfield_10369";
+pub const DEFAULT_ENTRY_TEMPLATE: &str = "{url}";
+
+/// Rules deciding how deliveries of a repository are handled, the first matching rule wins
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RoutingConfig {
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<Rule>,
+    /// Additional Jira sites next to the `default` site configured via environment
+    #[serde(default)]
+    pub sites: BTreeMap<String, SiteConfig>,
+    /// Environment variables holding the webhook secret by repository full name or owner
+    #[serde(default)]
+    pub webhook_secrets: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SiteConfig {
+    pub url: String,
+    /// `cloud` or `datacenter`
+    pub flavor: Option<String>,
+    pub email: Option<String>,
+    /// Environment variable holding the API token, or `<token_var>_KMS` holding it KMS encrypted
+    pub token_var: Option<String>,
+    pub client_id: Option<String>,
+    /// Environment variable holding the OAuth client secret, or `<client_secret_var>_KMS`
+    pub client_secret_var: Option<String>,
+    pub token_url: Option<String>,
+    /// Issues of these projects are resolved to this site unless a rule names a site
+    #[serde(default)]
+    pub projects: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Full name of the repository, e.g. `example-org/widgets`
+    pub repository: Option<String>,
+    /// Login of the organization or user owning the repository
+    pub organization: Option<String>,
+    /// Site issues are updated on, resolved by project key if not set
+    pub jira_site: Option<String>,
+    /// Project keys issues may belong to, any project if empty
+    #[serde(default)]
+    pub projects: Vec<String>,
+    #[serde(default = "default_checklist_field")]
+    pub checklist_field: String,
+    /// Checklist entry after the status prefix, supports `{url}`, `{repository}`, `{number}` and `{title}`
+    #[serde(default = "default_entry_template")]
+    pub entry_template: String,
+    /// Overrides the global `DRY_RUN` setting
+    pub dry_run: Option<bool>,
+}
+
+fn default_checklist_field() -> String {
+    DEFAULT_CHECKLIST_FIELD.to_string()
+}
+
+fn default_entry_template() -> String {
+    DEFAULT_ENTRY_TEMPLATE.to_string()
+}
+
+impl Default for RoutingConfig {
+    /// Without a config file, every repository is handled the same way
+    fn default() -> Self {
+        Self {
+            rules: vec![Rule::catch_all()],
+            sites: BTreeMap::new(),
+            webhook_secrets: BTreeMap::new(),
+        }
+    }
+}
+
+impl RoutingConfig {
+    pub fn from_toml(toml: &str) -> Result<Self, Error> {
+        let config: Self =
+            toml::from_str(toml).map_err(|e| Error::InvalidConfigFile(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        for rule in &self.rules {
+            if !rule.entry_template.contains("{url}") {
+                return Err(Error::InvalidConfigFile(format!(
+                    "entry template `{}` must contain {{url}}",
+                    rule.entry_template
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn route(&self, repository: &str) -> Result<&Rule, Error> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(repository))
+            .ok_or_else(|| Error::UnknownRepository(repository.to_string()))
+    }
+
+    /// The site named by the rule, else the site owning the project of the issue, else the default site
+    pub fn site_for<'a>(&'a self, rule: &'a Rule, issue_key: &str) -> &'a str {
+        match &rule.jira_site {
+            Some(site) => site,
+            None => self.site_for_project(issue_key),
+        }
+    }
+
+    /// The site owning the project of the issue, else the default site
+    pub fn site_for_project(&self, issue_key: &str) -> &str {
+        let project = project_key(issue_key);
+        self.sites
+            .iter()
+            .find(|(_, site)| {
+                site.projects
+                    .iter()
+                    .any(|owned| owned.eq_ignore_ascii_case(project))
+            })
+            .map(|(name, _)| name.as_str())
+            .unwrap_or(DEFAULT_SITE)
+    }
+
+    /// Names of all sites rules or issues may be routed to
+    pub fn referenced_sites(&self) -> impl Iterator<Item = &str> {
+        self.rules
+            .iter()
+            .filter_map(|rule| rule.jira_site.as_deref())
+            .chain(self.sites.keys().map(String::as_str))
+    }
+}
+
+fn project_key(issue_key: &str) -> &str {
+    issue_key.split('-').next().unwrap_or_default()
+}
+
+impl Rule {
+    fn catch_all() -> Self {
+        Self {
+            repository: None,
+            organization: None,
+            jira_site: None,
+            projects: Vec::new(),
+            checklist_field: default_checklist_field(),
+            entry_template: default_entry_template(),
+            dry_run: None,
+        }
+    }
+
+    /// GitHub treats owner and repository names case-insensitively
+    fn matches(&self, repository: &str) -> bool {
+        let organization = repository.split('/').next().unwrap_or_default();
+
+        self.repository
+            .as_ref()
+            .is_none_or(|name| name.eq_ignore_ascii_case(repository))
+            && self
+                .organization
+                .as_ref()
+                .is_none_or(|name| name.eq_ignore_ascii_case(organization))
+    }
+
+    pub fn allows_issue(&self, issue_key: &str) -> bool {
+        let project = project_key(issue_key);
+        self.projects.is_empty()
+            || self
+                .projects
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(project))
+    }
+
+    pub fn dry_run(&self, default: bool) -> bool {
+        self.dry_run.unwrap_or(default)
+    }
+
+    /// Substitutes the placeholders in one pass, so placeholders in the title stay as they are
+    pub fn render_entry(&self, payload: &PullRequestPayload) -> String {
+        let number = payload.pull_request.number.to_string();
+        let placeholders = [
+            ("{repository}", payload.repository.full_name.as_str()),
+            ("{number}", number.as_str()),
+            ("{title}", payload.pull_request.title.as_str()),
+            ("{url}", payload.pull_request.html_url.as_str()),
+        ];
+
+        let mut entry = String::with_capacity(self.entry_template.len());
+        let mut rest = self.entry_template.as_str();
+        while let Some(start) = rest.find('{') {
+            entry.push_str(&rest[..start]);
+            rest = &rest[start..];
+            match placeholders
+                .iter()
+                .find(|(placeholder, _)| rest.starts_with(placeholder))
+            {
+                Some((placeholder, value)) => {
+                    entry.push_str(value);
+                    rest = &rest[placeholder.len()..];
+                }
+                None => {
+                    entry.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        entry.push_str(rest);
+        entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        [[rule]]
+        repository = "example-org/legacy"
+        dry_run = true
+
+        [[rule]]
+        organization = "example-org"
+        projects = ["ABC", "DEF"]
+        checklist_field = "customfield_10100"
+        entry_template = "{url} ({repository}#{number})"
+    "#;
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let config = RoutingConfig::from_toml(CONFIG).unwrap();
+
+        let legacy = config.route("example-org/legacy").unwrap();
+        assert!(legacy.dry_run(false));
+        assert_eq!(legacy.checklist_field, DEFAULT_CHECKLIST_FIELD);
+
+        let widgets = config.route("Example-Org/widgets").unwrap();
+        assert!(!widgets.dry_run(false));
+        assert_eq!(widgets.checklist_field, "customfield_10100");
+        assert_eq!(widgets.jira_site, None);
+    }
+
+    #[test]
+    fn unknown_repository_is_rejected() {
+        let config = RoutingConfig::from_toml(CONFIG).unwrap();
+
+        let error = config
+            .route("other-org/widgets")
+            .expect_err("Expected error");
+
+        assert!(matches!(error, Error::UnknownRepository(name) if name == "other-org/widgets"));
+    }
+
+    #[test]
+    fn default_config_routes_everything() {
+        let config = RoutingConfig::default();
+
+        let rule = config.route("other-org/widgets").unwrap();
+
+        assert!(rule.allows_issue("XYZ-1"));
+        assert_eq!(rule.entry_template, DEFAULT_ENTRY_TEMPLATE);
+    }
+
+    #[test]
+    fn restricts_projects() {
+        let config = RoutingConfig::from_toml(CONFIG).unwrap();
+        let rule = config.route("example-org/widgets").unwrap();
+
+        assert!(rule.allows_issue("ABC-123"));
+        assert!(rule.allows_issue("def-1"));
+        assert!(!rule.allows_issue("XYZ-1"));
+    }
+
+    #[test]
+    fn renders_entry_template() {
+        let config = RoutingConfig::from_toml(CONFIG).unwrap();
+        let rule = config.route("example-org/widgets").unwrap();
+        let payload: PullRequestPayload =
+            serde_json::from_str(include_str!("../tests/fixtures/pull_request_opened.json"))
+                .unwrap();
+
+        assert_eq!(
+            rule.render_entry(&payload),
+            "https://github.com/example-org/widgets/pull/42 (example-org/widgets#42)"
+        );
+    }
+
+    #[test]
+    fn placeholders_in_title_are_not_expanded() {
+        let rule = Rule {
+            entry_template: "{title} {url} {unknown}".to_string(),
+            ..RoutingConfig::default().rules.remove(0)
+        };
+        let mut payload: PullRequestPayload =
+            serde_json::from_str(include_str!("../tests/fixtures/pull_request_opened.json"))
+                .unwrap();
+        payload.pull_request.title = "[ABC-1] Document {url} and {number}".to_string();
+
+        assert_eq!(
+            rule.render_entry(&payload),
+            "[ABC-1] Document {url} and {number} https://github.com/example-org/widgets/pull/42 {unknown}"
+        );
+    }
+
+    #[test]
+    fn resolves_site_by_rule_or_project() {
+        let config = RoutingConfig::from_toml(
+            r#"
+            [[rule]]
+            repository = "acquired-org/app"
+            jira_site = "acquired"
+
+            [[rule]]
+
+            [sites.acquired]
+            url = "https://acquired.atlassian.net"
+            token_var = "JIRA_ACQUIRED_TOKEN"
+            email = "bot@acquired.example"
+            projects = ["ACQ"]
+            "#,
+        )
+        .unwrap();
+
+        let pinned = config.route("acquired-org/app").unwrap();
+        assert_eq!(config.site_for(pinned, "ABC-1"), "acquired");
+
+        let other = config.route("example-org/widgets").unwrap();
+        assert_eq!(config.site_for(other, "ACQ-7"), "acquired");
+        assert_eq!(config.site_for(other, "ABC-1"), DEFAULT_SITE);
+    }
+
+    #[test]
+    fn rejects_invalid_config() {
+        RoutingConfig::from_toml("[[rule]]\nrepo = \"example-org/widgets\"")
+            .expect_err("Expected unknown field error");
+        RoutingConfig::from_toml("[[rule]]\nentry_template = \"{title}\"")
+            .expect_err("Expected missing url error");
+    }
+}
@@ -0,0 +1,167 @@
+use super::{BoxFuture, Env, SecretSource};
+use crate::error::Error;
+use crate::types::Secret;
+
+pub const SECRETS_MANAGER_SUFFIX: &str = "_SECRET_ID";
+pub const SSM_SUFFIX: &str = "_SSM_PARAMETER";
+const SECRETS_MANAGER_ENDPOINT_URL: &str = "SECRETS_MANAGER_ENDPOINT_URL";
+const SSM_ENDPOINT_URL: &str = "SSM_ENDPOINT_URL";
+
+/// Secret string of a Secrets Manager secret, by name or ARN
+pub struct SecretsManagerSource(aws_sdk_secretsmanager::Client);
+
+impl SecretsManagerSource {
+    pub fn new(client: aws_sdk_secretsmanager::Client) -> Self {
+        Self(client)
+    }
+
+    /// The endpoint can be overridden with `SECRETS_MANAGER_ENDPOINT_URL`
+    pub fn from_env(aws_config: &aws_config::SdkConfig, env: &Env) -> Self {
+        let mut config = aws_sdk_secretsmanager::config::Builder::from(aws_config);
+        if let Ok(url) = env.var(SECRETS_MANAGER_ENDPOINT_URL) {
+            config = config.endpoint_url(url);
+        }
+        Self::new(aws_sdk_secretsmanager::Client::from_conf(config.build()))
+    }
+}
+
+impl SecretSource for SecretsManagerSource {
+    fn fetch<'a>(
+        &'a self,
+        _: &'a Env,
+        _: &'a str,
+        id: &'a str,
+    ) -> BoxFuture<'a, Result<Secret, Error>> {
+        Box::pin(async move {
+            let secret = self
+                .0
+                .get_secret_value()
+                .secret_id(id)
+                .send()
+                .await
+                .map_err(|e| Error::SecretsManager(Box::new(e.into())))?;
+
+            secret
+                .secret_string()
+                .map(Secret::from)
+                .ok_or_else(|| Error::Internal(format!("secret {id} has no secret string")))
+        })
+    }
+}
+
+/// Value of an SSM parameter, SecureString parameters are decrypted
+pub struct SsmSource(aws_sdk_ssm::Client);
+
+impl SsmSource {
+    pub fn new(client: aws_sdk_ssm::Client) -> Self {
+        Self(client)
+    }
+
+    /// The endpoint can be overridden with `SSM_ENDPOINT_URL`
+    pub fn from_env(aws_config: &aws_config::SdkConfig, env: &Env) -> Self {
+        let mut config = aws_sdk_ssm::config::Builder::from(aws_config);
+        if let Ok(url) = env.var(SSM_ENDPOINT_URL) {
+            config = config.endpoint_url(url);
+        }
+        Self::new(aws_sdk_ssm::Client::from_conf(config.build()))
+    }
+}
+
+impl SecretSource for SsmSource {
+    fn fetch<'a>(
+        &'a self,
+        _: &'a Env,
+        _: &'a str,
+        id: &'a str,
+    ) -> BoxFuture<'a, Result<Secret, Error>> {
+        Box::pin(async move {
+            let parameter = self
+                .0
+                .get_parameter()
+                .name(id)
+                .with_decryption(true)
+                .send()
+                .await
+                .map_err(|e| Error::Ssm(Box::new(e.into())))?;
+
+            parameter
+                .parameter()
+                .and_then(|parameter| parameter.value())
+                .map(Secret::from)
+                .ok_or_else(|| Error::Internal(format!("parameter {id} has no value")))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::test_sdk_config as sdk_config;
+    use wiremock::matchers::{body_json, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn reads_secrets_manager_secret() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("x-amz-target", "secretsmanager.GetSecretValue"))
+            .and(body_json(
+                serde_json::json!({ "SecretId": "prod/jira-token" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "Name": "prod/jira-token",
+                "SecretString": "secret-token"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let source =
+            SecretsManagerSource::new(aws_sdk_secretsmanager::Client::new(&sdk_config(&server)));
+
+        assert_eq!(
+            source
+                .fetch(&Env::default(), "JIRA_TOKEN_SECRET_ID", "prod/jira-token")
+                .await
+                .unwrap()
+                .expose(),
+            "secret-token"
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_ssm_parameter() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("x-amz-target", "AmazonSSM.GetParameter"))
+            .and(body_json(serde_json::json!({
+                "Name": "/github-webhook/jira-token",
+                "WithDecryption": true
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "Parameter": {
+                    "Name": "/github-webhook/jira-token",
+                    "Type": "SecureString",
+                    "Value": "parameter-token"
+                }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let source = SsmSource::new(aws_sdk_ssm::Client::new(&sdk_config(&server)));
+
+        assert_eq!(
+            source
+                .fetch(
+                    &Env::default(),
+                    "JIRA_TOKEN_SSM_PARAMETER",
+                    "/github-webhook/jira-token"
+                )
+                .await
+                .unwrap()
+                .expose(),
+            "parameter-token"
+        );
+    }
+}
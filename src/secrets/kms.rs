@@ -0,0 +1,187 @@
+use super::{BoxFuture, Env, SecretSource};
+use crate::error::Error;
+use crate::types::Secret;
+use aws_sdk_kms::primitives::Blob;
+use std::collections::HashMap;
+
+pub const SUFFIX: &str = "_KMS";
+const KMS_ENCRYPTION_CONTEXT: &str = "KMS_ENCRYPTION_CONTEXT";
+const KMS_KEY_ID: &str = "KMS_KEY_ID";
+
+/// Base64 ciphertext decrypted by KMS
+///
+/// The encryption context is read as JSON object from `<VAR>_CONTEXT`, the expected key from `<VAR>_KEY_ID`,
+/// falling back to `KMS_ENCRYPTION_CONTEXT` and `KMS_KEY_ID` for all variables.
+pub struct KmsSource(aws_sdk_kms::Client);
+
+impl KmsSource {
+    pub fn new(client: aws_sdk_kms::Client) -> Self {
+        Self(client)
+    }
+
+    pub fn from_env(aws_config: &aws_config::SdkConfig) -> Self {
+        Self::new(aws_sdk_kms::Client::new(aws_config))
+    }
+}
+
+fn kms_setting(
+    env: &Env,
+    env_var_name: &str,
+    suffix: &str,
+    global: &str,
+) -> Option<(String, String)> {
+    let specific = format!("{env_var_name}{suffix}");
+    env.var(&specific)
+        .map(|value| (specific, value))
+        .or_else(|_| env.var(global).map(|value| (global.to_string(), value)))
+        .ok()
+}
+
+/// KMS reports the key ARN, the expected key may also be configured as bare key ID
+fn is_expected_key(key_id: &str, expected: &str) -> bool {
+    key_id == expected || key_id.ends_with(&format!("key/{expected}"))
+}
+
+impl SecretSource for KmsSource {
+    fn fetch<'a>(
+        &'a self,
+        env: &'a Env,
+        env_var_name: &'a str,
+        id: &'a str,
+    ) -> BoxFuture<'a, Result<Secret, Error>> {
+        Box::pin(async move {
+            use base64::{engine::general_purpose::STANDARD as Base64, Engine as _};
+            let encrypted_bytes = Base64.decode(id).map_err(|_| Error::EnvVarBadValue {
+                env_var_name: env_var_name.to_string(),
+            })?;
+
+            let encryption_context =
+                match kms_setting(env, env_var_name, "_CONTEXT", KMS_ENCRYPTION_CONTEXT) {
+                    None => None,
+                    Some((name, context)) => Some(
+                        serde_json::from_str::<HashMap<String, String>>(&context)
+                            .map_err(|_| Error::EnvVarBadValue { env_var_name: name })?,
+                    ),
+                };
+            let expected_key =
+                kms_setting(env, env_var_name, "_KEY_ID", KMS_KEY_ID).map(|(_, key)| key);
+
+            let decrypted = self
+                .0
+                .decrypt()
+                .ciphertext_blob(Blob::from(encrypted_bytes))
+                .set_encryption_context(encryption_context)
+                .set_key_id(expected_key.clone())
+                .send()
+                .await
+                .map_err(|e| Error::AwsKms {
+                    env_var_name: env_var_name.to_string(),
+                    source: Box::new(e.into()),
+                })?;
+
+            if let Some(expected_key) = expected_key {
+                let key_id = decrypted.key_id().unwrap_or_default();
+                if !is_expected_key(key_id, &expected_key) {
+                    return Err(Error::KmsKeyMismatch {
+                        env_var_name: env_var_name.to_string(),
+                        key_id: key_id.to_string(),
+                    });
+                }
+            }
+
+            let decrypted = decrypted.plaintext().ok_or(Error::Internal(
+                "decrypted value had no plain text".to_string(),
+            ))?;
+
+            Ok(Secret::new(
+                String::from_utf8_lossy(decrypted.as_ref()).to_string(),
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::test_sdk_config as sdk_config;
+    use wiremock::matchers::{body_json, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const KEY_ARN: &str =
+        "arn:aws:kms:eu-central-1:123456789012:key/1234abcd-12ab-34cd-56ef-1234567890ab";
+
+    async fn mock_decrypt(server: &MockServer, key_id: &str) {
+        use base64::{engine::general_purpose::STANDARD as Base64, Engine as _};
+
+        Mock::given(method("POST"))
+            .and(header("x-amz-target", "TrentService.Decrypt"))
+            .and(body_json(serde_json::json!({
+                "CiphertextBlob": Base64.encode("ciphertext"),
+                "EncryptionContext": { "LambdaFunctionName": "github-webhook" },
+                "KeyId": "1234abcd-12ab-34cd-56ef-1234567890ab"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "KeyId": key_id,
+                "Plaintext": Base64.encode("kms-token")
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn decrypts_with_context_and_pinned_key() {
+        use base64::{engine::general_purpose::STANDARD as Base64, Engine as _};
+
+        let server = MockServer::start().await;
+        mock_decrypt(&server, KEY_ARN).await;
+        let env = Env::from_iter([
+            (
+                "JIRA_TOKEN_KMS_CONTEXT",
+                r#"{"LambdaFunctionName": "github-webhook"}"#,
+            ),
+            (
+                "JIRA_TOKEN_KMS_KEY_ID",
+                "1234abcd-12ab-34cd-56ef-1234567890ab",
+            ),
+        ]);
+        let source = KmsSource::new(aws_sdk_kms::Client::new(&sdk_config(&server)));
+
+        let value = source
+            .fetch(&env, "JIRA_TOKEN_KMS", &Base64.encode("ciphertext"))
+            .await
+            .unwrap();
+
+        assert_eq!(value.expose(), "kms-token");
+    }
+
+    #[tokio::test]
+    async fn rejects_other_key() {
+        use base64::{engine::general_purpose::STANDARD as Base64, Engine as _};
+
+        let server = MockServer::start().await;
+        mock_decrypt(
+            &server,
+            "arn:aws:kms:eu-central-1:123456789012:key/ffffffff-12ab-34cd-56ef-1234567890ab",
+        )
+        .await;
+        let env = Env::from_iter([
+            (
+                "KMS_ENCRYPTION_CONTEXT",
+                r#"{"LambdaFunctionName": "github-webhook"}"#,
+            ),
+            ("KMS_KEY_ID", "1234abcd-12ab-34cd-56ef-1234567890ab"),
+        ]);
+        let source = KmsSource::new(aws_sdk_kms::Client::new(&sdk_config(&server)));
+
+        let error = source
+            .fetch(&env, "JIRA_TOKEN_KMS", &Base64.encode("ciphertext"))
+            .await
+            .expect_err("Expected key mismatch");
+
+        assert!(matches!(
+            error,
+            Error::KmsKeyMismatch { env_var_name, .. } if env_var_name == "JIRA_TOKEN_KMS"
+        ));
+    }
+}
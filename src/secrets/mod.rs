@@ -0,0 +1,334 @@
+use crate::error::Error;
+use crate::types::Secret;
+use std::collections::{BTreeMap, HashMap};
+use std::env::VarError;
+use std::ffi::{OsStr, OsString};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "aws-secrets")]
+mod aws;
+#[cfg(feature = "aws-kms")]
+mod kms;
+
+#[cfg(feature = "aws-secrets")]
+pub use aws::{SecretsManagerSource, SsmSource};
+#[cfg(feature = "aws-kms")]
+pub use kms::KmsSource;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+const SECRETS_REFRESH_INTERVAL: &str = "SECRETS_REFRESH_INTERVAL";
+
+/// Environment variables the configuration is read from, a snapshot of the process environment
+/// unless built from pairs, e.g. in tests
+#[derive(Debug, Clone, Default)]
+pub struct Env(BTreeMap<OsString, OsString>);
+
+impl Env {
+    pub fn process() -> Self {
+        Self(std::env::vars_os().collect())
+    }
+
+    /// Like `std::env::var`
+    pub fn var(&self, name: &str) -> Result<String, VarError> {
+        let value = self.0.get(OsStr::new(name)).ok_or(VarError::NotPresent)?;
+        value.clone().into_string().map_err(VarError::NotUnicode)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &OsStr> {
+        self.0.keys().map(OsString::as_os_str)
+    }
+}
+
+impl<K: Into<OsString>, V: Into<OsString>> FromIterator<(K, V)> for Env {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(vars: I) -> Self {
+        Self(
+            vars.into_iter()
+                .map(|(name, value)| (name.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+/// Backend holding secret values, e.g. KMS or Secrets Manager
+pub trait SecretSource: Send + Sync {
+    /// Resolves the id found in the environment variable `env_var_name` to the secret value,
+    /// settings of the source are read from `env`
+    fn fetch<'a>(
+        &'a self,
+        env: &'a Env,
+        env_var_name: &'a str,
+        id: &'a str,
+    ) -> BoxFuture<'a, Result<Secret, Error>>;
+}
+
+struct CachedSecret {
+    value: Secret,
+    fetched_at: Instant,
+}
+
+/// Resolves secrets configured as `<NAME>` in plain text or as `<NAME><suffix>` in one of the sources,
+/// values of sources are cached until the refresh interval elapsed
+pub struct SecretStore {
+    env: Env,
+    sources: Vec<(&'static str, Box<dyn SecretSource>)>,
+    refresh_interval: Option<Duration>,
+    cache: Mutex<HashMap<String, CachedSecret>>,
+}
+
+impl SecretStore {
+    pub fn new(refresh_interval: Option<Duration>) -> Self {
+        Self {
+            env: Env::process(),
+            sources: Vec::new(),
+            refresh_interval,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Plain environment variables plus the sources enabled by cargo features, AWS config is only loaded
+    /// if a variable refers to an AWS source
+    pub async fn from_env() -> Result<Self, Error> {
+        let mut store = Self::new(None);
+        store.refresh_interval = match store.var(SECRETS_REFRESH_INTERVAL) {
+            Err(_) => None,
+            Ok(seconds) => Some(Duration::from_secs(seconds.parse().map_err(|_| {
+                Error::EnvVarBadValue {
+                    env_var_name: SECRETS_REFRESH_INTERVAL.to_string(),
+                }
+            })?)),
+        };
+
+        #[cfg(any(feature = "aws-kms", feature = "aws-secrets"))]
+        let store = store.with_aws_sources().await;
+
+        Ok(store)
+    }
+
+    #[cfg(any(feature = "aws-kms", feature = "aws-secrets"))]
+    async fn with_aws_sources(mut self) -> Self {
+        let suffixes = [
+            #[cfg(feature = "aws-kms")]
+            kms::SUFFIX,
+            #[cfg(feature = "aws-secrets")]
+            aws::SECRETS_MANAGER_SUFFIX,
+            #[cfg(feature = "aws-secrets")]
+            aws::SSM_SUFFIX,
+        ];
+        let uses_aws = self
+            .env
+            .names()
+            .filter_map(OsStr::to_str)
+            .any(|name| suffixes.iter().any(|suffix| name.ends_with(suffix)));
+        if !uses_aws {
+            return self;
+        }
+
+        let aws_config =
+            aws_config::load_defaults(aws_config::BehaviorVersion::v2026_01_12()).await;
+        #[cfg(feature = "aws-kms")]
+        {
+            self = self.with_source(kms::SUFFIX, KmsSource::from_env(&aws_config));
+        }
+        #[cfg(feature = "aws-secrets")]
+        {
+            let secrets_manager = SecretsManagerSource::from_env(&aws_config, &self.env);
+            let ssm = SsmSource::from_env(&aws_config, &self.env);
+            self = self
+                .with_source(aws::SECRETS_MANAGER_SUFFIX, secrets_manager)
+                .with_source(aws::SSM_SUFFIX, ssm);
+        }
+        self
+    }
+
+    pub fn with_source(
+        mut self,
+        suffix: &'static str,
+        source: impl SecretSource + 'static,
+    ) -> Self {
+        self.sources.push((suffix, Box::new(source)));
+        self
+    }
+
+    /// Reads variables from `env` instead of the process environment
+    pub fn with_env(mut self, env: Env) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Value of the environment variable
+    pub fn var(&self, name: &str) -> Result<String, VarError> {
+        self.env.var(name)
+    }
+
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        self.refresh_interval
+    }
+
+    /// Whether the secret is configured at all
+    pub fn is_set(&self, name: &str) -> bool {
+        self.var(name).is_ok()
+            || self
+                .sources
+                .iter()
+                .any(|(suffix, _)| self.var(&format!("{name}{suffix}")).is_ok())
+    }
+
+    pub async fn get(&self, name: &str) -> Result<Secret, Error> {
+        for (suffix, source) in &self.sources {
+            let env_var_name = format!("{name}{suffix}");
+            let Ok(id) = self.var(&env_var_name) else {
+                continue;
+            };
+
+            let cache_key = format!("{suffix}:{id}");
+            if let Some(value) = self.cached(&cache_key) {
+                return Ok(value);
+            }
+
+            let value = source.fetch(&self.env, &env_var_name, &id).await?;
+            self.cache.lock().unwrap().insert(
+                cache_key,
+                CachedSecret {
+                    value: value.clone(),
+                    fetched_at: Instant::now(),
+                },
+            );
+            return Ok(value);
+        }
+
+        // fall back to plain text
+        self.var(name)
+            .map(Secret::from)
+            .map_err(|_| Error::EnvVarNotSet {
+                env_var_name: name.to_string(),
+            })
+    }
+
+    fn cached(&self, cache_key: &str) -> Option<Secret> {
+        let cache = self.cache.lock().unwrap();
+        let cached = cache.get(cache_key)?;
+        let fresh = self
+            .refresh_interval
+            .is_none_or(|interval| cached.fetched_at.elapsed() < interval);
+        fresh.then(|| cached.value.clone())
+    }
+}
+
+/// AWS config pointing all clients at a local mock
+#[cfg(all(
+    test,
+    any(
+        feature = "aws-kms",
+        feature = "aws-secrets",
+        feature = "aws-dynamodb",
+        feature = "aws-sqs",
+        feature = "aws-s3"
+    )
+))]
+pub(crate) fn test_sdk_config(server: &wiremock::MockServer) -> aws_config::SdkConfig {
+    use aws_config::{BehaviorVersion, Region};
+    use aws_credential_types::{provider::SharedCredentialsProvider, Credentials};
+
+    aws_config::SdkConfig::builder()
+        .behavior_version(BehaviorVersion::latest())
+        .region(Region::new("eu-central-1"))
+        .credentials_provider(SharedCredentialsProvider::new(Credentials::for_tests()))
+        .endpoint_url(server.uri())
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct CountingSource(Arc<AtomicUsize>);
+
+    impl SecretSource for CountingSource {
+        fn fetch<'a>(
+            &'a self,
+            _: &'a Env,
+            _: &'a str,
+            id: &'a str,
+        ) -> BoxFuture<'a, Result<Secret, Error>> {
+            Box::pin(async move {
+                let count = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+                Ok(Secret::from(format!("{id}-{count}")))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn caches_until_refresh_interval_elapsed() {
+        let env = Env::from_iter([("SECRETS_TEST_CACHED_TOKEN_FAKE", "token")]);
+        let source = CountingSource::default();
+        let store = SecretStore::new(None)
+            .with_env(env.clone())
+            .with_source("_FAKE", source.clone());
+
+        assert_eq!(
+            store
+                .get("SECRETS_TEST_CACHED_TOKEN")
+                .await
+                .unwrap()
+                .expose(),
+            "token-1"
+        );
+        assert_eq!(
+            store
+                .get("SECRETS_TEST_CACHED_TOKEN")
+                .await
+                .unwrap()
+                .expose(),
+            "token-1"
+        );
+
+        let store = SecretStore::new(Some(Duration::ZERO))
+            .with_env(env)
+            .with_source("_FAKE", source);
+        assert_eq!(
+            store
+                .get("SECRETS_TEST_CACHED_TOKEN")
+                .await
+                .unwrap()
+                .expose(),
+            "token-2"
+        );
+        assert_eq!(
+            store
+                .get("SECRETS_TEST_CACHED_TOKEN")
+                .await
+                .unwrap()
+                .expose(),
+            "token-3"
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_plain_text() {
+        let store = SecretStore::new(None)
+            .with_env(Env::from_iter([("SECRETS_TEST_PLAIN_TOKEN", "plain")]))
+            .with_source("_FAKE", CountingSource::default());
+
+        assert!(store.is_set("SECRETS_TEST_PLAIN_TOKEN"));
+        assert_eq!(
+            store
+                .get("SECRETS_TEST_PLAIN_TOKEN")
+                .await
+                .unwrap()
+                .expose(),
+            "plain"
+        );
+        assert!(!store.is_set("SECRETS_TEST_MISSING_TOKEN"));
+        store
+            .get("SECRETS_TEST_MISSING_TOKEN")
+            .await
+            .expect_err("Expected missing variable");
+    }
+}
@@ -0,0 +1,418 @@
+use crate::error::{Error, Result};
+use crate::http_handler;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::StatusCode;
+use hyper_util::rt::{TokioIo, TokioTimer};
+use hyper_util::server::graceful::GracefulShutdown;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::{self, crypto::ring};
+use tokio_rustls::TlsAcceptor;
+
+const SERVER_BIND_ADDRESS: &str = "SERVER_BIND_ADDRESS";
+const SERVER_BODY_LIMIT: &str = "SERVER_BODY_LIMIT";
+const SERVER_TLS_CERT_FILE: &str = "SERVER_TLS_CERT_FILE";
+const SERVER_TLS_KEY_FILE: &str = "SERVER_TLS_KEY_FILE";
+
+const DEFAULT_BIND_ADDRESS: &str = "0.0.0.0:8080";
+/// GitHub caps webhook payloads at 25 MB
+const DEFAULT_BODY_LIMIT: usize = 25 * 1024 * 1024;
+/// Pause after running out of file descriptors, until open connections are closed
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// How long a client may take before its request is read, so idle clients don't hold connections
+#[derive(Debug, Clone, Copy)]
+pub struct Timeouts {
+    pub tls_handshake: Duration,
+    pub header_read: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            tls_handshake: Duration::from_secs(10),
+            header_read: Duration::from_secs(30),
+        }
+    }
+}
+
+pub struct ServerConfig {
+    pub bind_address: SocketAddr,
+    /// Bodies larger than this are rejected with `413 Payload Too Large`
+    pub body_limit: usize,
+    pub timeouts: Timeouts,
+    /// Certificate chain and private key in PEM format, plain HTTP if not set
+    pub tls: Option<(PathBuf, PathBuf)>,
+}
+
+impl ServerConfig {
+    pub fn from_env() -> Result<Self> {
+        use std::env::var;
+
+        let bad_value = |env_var_name: &str| Error::EnvVarBadValue {
+            env_var_name: env_var_name.to_string(),
+        };
+
+        let bind_address = var(SERVER_BIND_ADDRESS)
+            .unwrap_or(DEFAULT_BIND_ADDRESS.to_string())
+            .parse()
+            .map_err(|_| bad_value(SERVER_BIND_ADDRESS))?;
+        let body_limit = match var(SERVER_BODY_LIMIT) {
+            Err(_) => DEFAULT_BODY_LIMIT,
+            Ok(limit) => limit.parse().map_err(|_| bad_value(SERVER_BODY_LIMIT))?,
+        };
+        let tls = match (var(SERVER_TLS_CERT_FILE), var(SERVER_TLS_KEY_FILE)) {
+            (Ok(cert_file), Ok(key_file)) => Some((cert_file.into(), key_file.into())),
+            (Err(_), Err(_)) => None,
+            (Ok(_), Err(_)) => {
+                return Err(Error::EnvVarNotSet {
+                    env_var_name: SERVER_TLS_KEY_FILE.to_string(),
+                })
+            }
+            (Err(_), Ok(_)) => {
+                return Err(Error::EnvVarNotSet {
+                    env_var_name: SERVER_TLS_CERT_FILE.to_string(),
+                })
+            }
+        };
+
+        Ok(Self {
+            bind_address,
+            body_limit,
+            timeouts: Timeouts::default(),
+            tls,
+        })
+    }
+
+    pub fn tls_acceptor(&self) -> Result<Option<TlsAcceptor>> {
+        let Some((cert_file, key_file)) = &self.tls else {
+            return Ok(None);
+        };
+
+        let certs = CertificateDer::pem_file_iter(cert_file)
+            .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+            .map_err(|e| Error::Tls(format!("failed to read {}: {e}", cert_file.display())))?;
+        let key = PrivateKeyDer::from_pem_file(key_file)
+            .map_err(|e| Error::Tls(format!("failed to read {}: {e}", key_file.display())))?;
+
+        let config =
+            rustls::ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+                .map_err(|e| Error::Tls(e.to_string()))?;
+
+        Ok(Some(TlsAcceptor::from(Arc::new(config))))
+    }
+}
+
+/// Serves `handler` until `shutdown` resolves, then waits for open connections to finish
+pub async fn serve<H, F>(
+    listener: TcpListener,
+    tls: Option<TlsAcceptor>,
+    body_limit: usize,
+    timeouts: Timeouts,
+    handler: H,
+    shutdown: impl Future<Output = ()>,
+) -> Result<()>
+where
+    H: Fn(http::Request<Bytes>) -> F + Clone + Send + Sync + 'static,
+    F: Future<Output = http::Response<String>> + Send,
+{
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+    let mut http = http1::Builder::new();
+    http.timer(TokioTimer::new())
+        .header_read_timeout(timeouts.header_read);
+
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            () = &mut shutdown => break,
+        };
+        let (stream, remote) = match accepted {
+            Ok(accepted) => accepted,
+            Err(error) => {
+                let Some(backoff) = accept_backoff(&error) else {
+                    return Err(error.into());
+                };
+                tracing::warn!(?error, "Failed to accept connection");
+                tokio::select! {
+                    () = tokio::time::sleep(backoff) => continue,
+                    () = &mut shutdown => break,
+                }
+            }
+        };
+
+        let handler = handler.clone();
+        let service = service_fn(move |request| {
+            let handler = handler.clone();
+            async move {
+                let response = match collect(request, body_limit).await {
+                    Ok(request) => handler(request).await,
+                    Err(response) => response,
+                };
+                Ok::<_, Infallible>(response.map(|body| Full::new(Bytes::from(body))))
+            }
+        });
+
+        match &tls {
+            None => {
+                let connection = http.serve_connection(TokioIo::new(stream), service);
+                let connection = graceful.watch(connection);
+                tokio::spawn(async move {
+                    if let Err(error) = connection.await {
+                        tracing::debug!(%remote, ?error, "Connection failed");
+                    }
+                });
+            }
+            Some(acceptor) => {
+                let acceptor = acceptor.clone();
+                let http = http.clone();
+                // shutdown waits for the watcher, so for the handshake as well
+                let watcher = graceful.watcher();
+                tokio::spawn(async move {
+                    let handshake = acceptor.accept(stream);
+                    let stream = match tokio::time::timeout(timeouts.tls_handshake, handshake).await
+                    {
+                        Ok(Ok(stream)) => stream,
+                        Ok(Err(error)) => {
+                            tracing::debug!(%remote, ?error, "TLS handshake failed");
+                            return;
+                        }
+                        Err(_) => {
+                            tracing::debug!(%remote, "TLS handshake timed out");
+                            return;
+                        }
+                    };
+                    let connection = http.serve_connection(TokioIo::new(stream), service);
+                    if let Err(error) = watcher.watch(connection).await {
+                        tracing::debug!(%remote, ?error, "Connection failed");
+                    }
+                });
+            }
+        }
+    }
+
+    tracing::info!("Shutting down, waiting for open connections");
+    graceful.shutdown().await;
+    Ok(())
+}
+
+/// How long to wait before accepting the next connection after the error, `None` if the listener
+/// itself failed
+fn accept_backoff(error: &std::io::Error) -> Option<Duration> {
+    use std::io::ErrorKind;
+
+    // EMFILE and ENFILE, the same numbers on Linux and macOS
+    const OUT_OF_FILE_DESCRIPTORS: [i32; 2] = [24, 23];
+
+    match error.kind() {
+        // only this connection failed, e.g. the client reset it before it was accepted
+        ErrorKind::ConnectionAborted
+        | ErrorKind::ConnectionReset
+        | ErrorKind::ConnectionRefused
+        | ErrorKind::Interrupted
+        | ErrorKind::TimedOut => Some(Duration::ZERO),
+        _ if error
+            .raw_os_error()
+            .is_some_and(|code| OUT_OF_FILE_DESCRIPTORS.contains(&code)) =>
+        {
+            Some(ACCEPT_BACKOFF)
+        }
+        _ => None,
+    }
+}
+
+/// Reads the whole body, rejecting it once it exceeds the limit
+async fn collect(
+    request: http::Request<Incoming>,
+    body_limit: usize,
+) -> std::result::Result<http::Request<Bytes>, http::Response<String>> {
+    let (parts, body) = request.into_parts();
+    let delivery_id = parts
+        .headers
+        .get("X-GitHub-Delivery")
+        .and_then(|v| v.to_str().ok());
+    match Limited::new(body, body_limit).collect().await {
+        Ok(body) => Ok(http::Request::from_parts(parts, body.to_bytes())),
+        Err(error) if error.is::<LengthLimitError>() => {
+            tracing::warn!(body_limit, "Rejected request body over the limit");
+            Err(http_handler::error_response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "payload_too_large",
+                delivery_id,
+            ))
+        }
+        Err(error) => {
+            tracing::warn!(?error, "Failed to read request body");
+            Err(http_handler::error_response(
+                StatusCode::BAD_REQUEST,
+                "unreadable_body",
+                delivery_id,
+            ))
+        }
+    }
+}
+
+/// Resolves on SIGTERM or Ctrl+C, only on Ctrl+C on platforms without unix signals
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut sigterm =
+            signal(SignalKind::terminate()).expect("SIGTERM handler can be installed");
+        tokio::select! {
+            _ = sigterm.recv() => {}
+            _ = tokio::signal::ctrl_c() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    async fn start(
+        body_limit: usize,
+    ) -> (String, oneshot::Sender<()>, tokio::task::JoinHandle<()>) {
+        start_with(body_limit, Timeouts::default()).await
+    }
+
+    async fn start_with(
+        body_limit: usize,
+        timeouts: Timeouts,
+    ) -> (String, oneshot::Sender<()>, tokio::task::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let (stop, stopped) = oneshot::channel();
+
+        let server = tokio::spawn(async move {
+            serve(
+                listener,
+                None,
+                body_limit,
+                timeouts,
+                |request: http::Request<Bytes>| async move {
+                    let status = if request.body().as_ref() == b"ok" {
+                        StatusCode::OK
+                    } else {
+                        StatusCode::NOT_FOUND
+                    };
+                    http_handler::error_response(status, "test", None)
+                },
+                async {
+                    stopped.await.ok();
+                },
+            )
+            .await
+            .unwrap();
+        });
+
+        (url, stop, server)
+    }
+
+    #[tokio::test]
+    async fn passes_body_to_handler() {
+        let (url, stop, server) = start(16).await;
+        let client = reqwest::Client::new();
+
+        let ok = client.post(&url).body("ok").send().await.unwrap();
+        let not_ok = client.post(&url).body("nope").send().await.unwrap();
+
+        assert_eq!(ok.status(), StatusCode::OK);
+        assert_eq!(not_ok.status(), StatusCode::NOT_FOUND);
+
+        stop.send(()).unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_body_over_limit() {
+        let (url, stop, server) = start(16).await;
+
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header("X-GitHub-Delivery", "delivery-1")
+            .body("x".repeat(17))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(
+            response.text().await.unwrap(),
+            r#"{"delivery_id":"delivery-1","error":"payload_too_large"}"#
+        );
+
+        stop.send(()).unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn closes_idle_connections() {
+        use tokio::io::AsyncReadExt;
+
+        let timeouts = Timeouts {
+            header_read: Duration::from_millis(100),
+            ..Timeouts::default()
+        };
+        let (url, stop, server) = start_with(16, timeouts).await;
+        let address = url.trim_start_matches("http://").trim_end_matches('/');
+
+        let mut idle = tokio::net::TcpStream::connect(address).await.unwrap();
+        let mut buffer = Vec::new();
+        // either end of stream or a reset, the connection is closed in both cases
+        let _ = tokio::time::timeout(Duration::from_secs(5), idle.read_to_end(&mut buffer))
+            .await
+            .expect("Expected the server to close the connection");
+
+        stop.send(()).unwrap();
+        server.await.unwrap();
+    }
+
+    #[test]
+    fn only_listener_failures_end_serving() {
+        use std::io::{Error, ErrorKind};
+
+        assert_eq!(
+            accept_backoff(&Error::from(ErrorKind::ConnectionAborted)),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            accept_backoff(&Error::from_raw_os_error(24)),
+            Some(ACCEPT_BACKOFF)
+        );
+        assert_eq!(accept_backoff(&Error::from(ErrorKind::InvalidInput)), None);
+    }
+
+    #[test]
+    fn missing_tls_file_is_reported() {
+        let config = ServerConfig {
+            bind_address: DEFAULT_BIND_ADDRESS.parse().unwrap(),
+            body_limit: DEFAULT_BODY_LIMIT,
+            timeouts: Timeouts::default(),
+            tls: Some(("missing-cert.pem".into(), "missing-key.pem".into())),
+        };
+
+        let error = config.tls_acceptor().err().expect("Expected TLS error");
+
+        assert!(error.to_string().contains("missing-cert.pem"), "{error}");
+    }
+}
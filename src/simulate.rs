@@ -0,0 +1,346 @@
+//! Signed deliveries for local testing, sent to a running webhook or handled in-process by the
+//! `github-webhook-simulate` binary
+
+use crate::deliveries::MemoryDeliveryStore;
+use crate::error::Error;
+use crate::github;
+use crate::http_handler::{self, WebhookRequest};
+use crate::jira::{IssueTracker, JiraRegistry};
+use crate::routing::RoutingConfig;
+use crate::secrets::SecretStore;
+use crate::state::State;
+use crate::types::{WebhookSecret, WebhookSecretMap};
+use chrono::Utc;
+use http::header::CONTENT_TYPE;
+use http::{HeaderMap, HeaderValue, StatusCode};
+use std::path::PathBuf;
+
+const WEBHOOK_SECRET: &str = "WEBHOOK_SECRET";
+
+pub const USAGE: &str = "\
+Usage: github-webhook-simulate [--event TYPE] [--payload FILE] [--url URL] [--secret SECRET]
+                               [--delivery-id ID] [--dry-run]
+                               [--title TITLE] [--action ACTION] [--merged]
+                               [--repository OWNER/NAME] [--number N]
+
+Without --payload a pull_request payload is built from --title, --action (default opened, closed
+if --merged), --repository (default example-org/widgets) and --number (default 1).
+Without --url the delivery is handled in-process with the configuration from the environment.
+The payload is signed with --secret, else the webhook secret of the configuration (WEBHOOK_SECRET
+when posting to a URL).
+";
+
+/// A `pull_request` payload with the fields the handler reads
+#[derive(Debug)]
+pub struct SyntheticPullRequest {
+    pub title: String,
+    pub action: Option<String>,
+    pub merged: bool,
+    pub repository: String,
+    pub number: u64,
+}
+
+impl Default for SyntheticPullRequest {
+    fn default() -> Self {
+        Self {
+            title: String::new(),
+            action: None,
+            merged: false,
+            repository: "example-org/widgets".to_string(),
+            number: 1,
+        }
+    }
+}
+
+impl SyntheticPullRequest {
+    pub fn payload(&self) -> serde_json::Value {
+        let action =
+            self.action
+                .as_deref()
+                .unwrap_or(if self.merged { "closed" } else { "opened" });
+        let closed = self.merged || action == "closed";
+        let now = Utc::now();
+
+        serde_json::json!({
+            "action": action,
+            "number": self.number,
+            "pull_request": {
+                "title": self.title,
+                "html_url": format!("https://github.com/{}/pull/{}", self.repository, self.number),
+                "number": self.number,
+                "state": if closed { "closed" } else { "open" },
+                "merged": self.merged,
+                "created_at": now,
+                "updated_at": now,
+                "closed_at": closed.then_some(now),
+                "merged_at": self.merged.then_some(now),
+            },
+            "repository": { "full_name": self.repository },
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct SimulateArgs {
+    pub event: String,
+    pub payload: Option<PathBuf>,
+    pub pull_request: SyntheticPullRequest,
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub delivery_id: String,
+    pub dry_run: bool,
+}
+
+impl SimulateArgs {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut simulate_args = Self {
+            event: "pull_request".to_string(),
+            payload: None,
+            pull_request: SyntheticPullRequest::default(),
+            url: None,
+            secret: None,
+            delivery_id: format!("simulated-{}", Utc::now().timestamp_millis()),
+            dry_run: false,
+        };
+        let mut synthetic = false;
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+            match arg.as_str() {
+                "--event" => simulate_args.event = value()?,
+                "--payload" => simulate_args.payload = Some(value()?.into()),
+                "--url" => simulate_args.url = Some(value()?),
+                "--secret" => simulate_args.secret = Some(value()?),
+                "--delivery-id" => simulate_args.delivery_id = value()?,
+                "--dry-run" => simulate_args.dry_run = true,
+                "--title" => simulate_args.pull_request.title = value()?,
+                "--action" => simulate_args.pull_request.action = Some(value()?),
+                "--merged" => simulate_args.pull_request.merged = true,
+                "--repository" => simulate_args.pull_request.repository = value()?,
+                "--number" => {
+                    simulate_args.pull_request.number = value()?
+                        .parse()
+                        .map_err(|_| "--number needs a number".to_string())?;
+                }
+                _ => return Err(format!("Unknown argument {arg}")),
+            }
+            synthetic |= [
+                "--title",
+                "--action",
+                "--merged",
+                "--repository",
+                "--number",
+            ]
+            .contains(&arg.as_str());
+        }
+
+        if simulate_args.payload.is_some() && synthetic {
+            return Err("--payload can't be combined with pull request flags".to_string());
+        }
+        if simulate_args.payload.is_none() && simulate_args.event != "pull_request" {
+            return Err(format!("{} needs a --payload", simulate_args.event));
+        }
+        Ok(simulate_args)
+    }
+}
+
+/// A delivery as GitHub would send it
+#[derive(Debug)]
+pub struct SimulatedDelivery {
+    pub event: String,
+    pub delivery_id: String,
+    pub body: Vec<u8>,
+    pub signature: String,
+}
+
+impl SimulatedDelivery {
+    pub fn new(
+        event: String,
+        delivery_id: String,
+        body: Vec<u8>,
+        secret: &WebhookSecret,
+    ) -> Result<Self, Error> {
+        let signature = github::sign(&body, secret)?;
+        Ok(Self {
+            event,
+            delivery_id,
+            body,
+            signature,
+        })
+    }
+
+    pub fn headers(&self) -> Result<HeaderMap, Error> {
+        let header = |value: &str| {
+            HeaderValue::from_str(value)
+                .map_err(|_| Error::Internal(format!("invalid header value {value}")))
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert("X-GitHub-Event", header(&self.event)?);
+        headers.insert("X-GitHub-Delivery", header(&self.delivery_id)?);
+        headers.insert("X-Hub-Signature-256", header(&self.signature)?);
+        Ok(headers)
+    }
+}
+
+/// Status and body of the response to the delivery
+pub async fn post(url: &str, delivery: &SimulatedDelivery) -> Result<(StatusCode, String), Error> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .headers(delivery.headers()?)
+        .body(delivery.body.clone())
+        .send()
+        .await?;
+    let status = response.status();
+    Ok((status, response.text().await?))
+}
+
+/// Handles the delivery like the webhook does, duplicates are only detected within the run
+pub async fn handle<T: IssueTracker>(
+    jira: JiraRegistry<T>,
+    webhook_secrets: &WebhookSecretMap,
+    routing: &RoutingConfig,
+    dry_run: bool,
+    delivery: &SimulatedDelivery,
+) -> Result<(StatusCode, String), Error> {
+    let headers = delivery.headers()?;
+    let request = WebhookRequest {
+        headers: &headers,
+        body: &delivery.body,
+    };
+    let result = http_handler::function_handler(
+        jira,
+        webhook_secrets,
+        &MemoryDeliveryStore::default(),
+        request,
+        routing,
+        dry_run,
+    )
+    .await;
+    let response = http_handler::response(result, request.delivery_id());
+    Ok((response.status(), response.into_body()))
+}
+
+/// Builds, signs and sends or handles the delivery
+pub async fn run(secrets: &SecretStore, args: SimulateArgs) -> Result<(StatusCode, String), Error> {
+    let body = match &args.payload {
+        Some(path) => tokio::fs::read(path)
+            .await
+            .map_err(|e| Error::Internal(format!("{}: {e}", path.display())))?,
+        None => serde_json::to_vec_pretty(&args.pull_request.payload())?,
+    };
+    let secret = args
+        .secret
+        .as_deref()
+        .map(|secret| WebhookSecret::new(secret.as_bytes()));
+
+    match &args.url {
+        Some(url) => {
+            let secret = match secret {
+                Some(secret) => secret?,
+                None => WebhookSecret::from_secret(secrets.get(WEBHOOK_SECRET).await?)?,
+            };
+            let delivery = SimulatedDelivery::new(args.event, args.delivery_id, body, &secret)?;
+            post(url, &delivery).await
+        }
+        None => {
+            let state = State::load(secrets).await?;
+            let secret = match secret {
+                Some(secret) => secret?,
+                None => github::select_secrets(&body, &state.webhook_secrets)?
+                    .current()
+                    .clone(),
+            };
+            let delivery = SimulatedDelivery::new(args.event, args.delivery_id, body, &secret)?;
+            handle(
+                state.jira,
+                &state.webhook_secrets,
+                &state.routing,
+                state.dry_run || args.dry_run,
+                &delivery,
+            )
+            .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::models::PullRequestPayload;
+    use crate::jira::fake::FakeIssueTracker;
+    use crate::routing::DEFAULT_SITE;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn parse(args: &[&str]) -> Result<SimulateArgs, String> {
+        SimulateArgs::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn builds_pull_request_payloads() {
+        let args = parse(&["--title", "[ABC-123] Change", "--merged"]).unwrap();
+        let payload: PullRequestPayload =
+            serde_json::from_value(args.pull_request.payload()).unwrap();
+
+        assert_eq!(payload.action, "closed");
+        assert!(payload.pull_request.merged);
+        assert_eq!(payload.pull_request.state, "closed");
+        assert_eq!(
+            payload.pull_request.html_url,
+            "https://github.com/example-org/widgets/pull/1"
+        );
+
+        parse(&["--payload", "push.json", "--title", "x"]).expect_err("Expected conflict");
+        parse(&["--event", "push"]).expect_err("Expected missing payload");
+    }
+
+    #[tokio::test]
+    async fn signed_delivery_is_handled() {
+        let secret = WebhookSecret::new("It's a Secret to Everybody").unwrap();
+        let args = parse(&[
+            "--title",
+            "[ABC-123] Change",
+            "--delivery-id",
+            "simulated-1",
+        ])
+        .unwrap();
+        let delivery = SimulatedDelivery::new(
+            args.event,
+            args.delivery_id,
+            serde_json::to_vec(&args.pull_request.payload()).unwrap(),
+            &secret,
+        )
+        .unwrap();
+
+        let tracker = FakeIssueTracker::default().with_issue(
+            "ABC-123",
+            Some("# Development Process\n-! Task 1: Create Pull Request\n# Pull Requests"),
+        );
+        let (status, body) = handle(
+            JiraRegistry::from_iter([(DEFAULT_SITE.to_string(), tracker.clone())]),
+            &WebhookSecretMap::from(secret),
+            &RoutingConfig::default(),
+            false,
+            &delivery,
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert!(body.contains(r#""result":"updated""#), "{body}");
+        assert_eq!(tracker.updates().len(), 1);
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/webhook"))
+            .and(header("x-github-event", "pull_request"))
+            .and(header("x-github-delivery", "simulated-1"))
+            .and(header("x-hub-signature-256", delivery.signature.as_str()))
+            .respond_with(ResponseTemplate::new(202).set_body_string("{}"))
+            .mount(&server)
+            .await;
+        let (status, _) = post(&format!("{}/webhook", server.uri()), &delivery)
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+}
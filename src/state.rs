@@ -0,0 +1,165 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::jira::{JiraClient, JiraRegistry};
+use crate::routing::RoutingConfig;
+use crate::secrets::SecretStore;
+use crate::types::WebhookSecretMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::RwLock;
+
+/// Everything built from the config, rebuilt once the secrets are due for a refresh
+pub struct State {
+    pub jira: JiraRegistry,
+    pub webhook_secrets: WebhookSecretMap,
+    pub routing: RoutingConfig,
+    pub dry_run: bool,
+}
+
+impl State {
+    pub async fn load(secrets: &SecretStore) -> Result<Self, Error> {
+        Self::build(secrets, None).await
+    }
+
+    /// Like `load`, the Jira clients keep the OAuth access tokens of the current ones
+    async fn reload(&self, secrets: &SecretStore) -> Result<Self, Error> {
+        Self::build(secrets, Some(&self.jira)).await
+    }
+
+    async fn build(secrets: &SecretStore, previous: Option<&JiraRegistry>) -> Result<Self, Error> {
+        let config = Config::from_env(secrets).await?;
+
+        Ok(State {
+            jira: config
+                .jira_sites
+                .into_iter()
+                .map(|(name, jira_config)| {
+                    let client = JiraClient::new(jira_config)?;
+                    let client = match previous.and_then(|previous| previous.site(&name).ok()) {
+                        Some(previous) => client.keep_token_of(previous),
+                        None => client,
+                    };
+                    Ok((name, client))
+                })
+                .collect::<Result<_, Error>>()?,
+            webhook_secrets: config.webhook_secrets,
+            routing: config.routing,
+            dry_run: config.dry_run,
+        })
+    }
+}
+
+struct Loaded {
+    state: Arc<State>,
+    /// Failed attempts count as well, so an outage is retried once per refresh interval and not
+    /// on every request
+    attempted_at: Instant,
+}
+
+pub struct SharedState {
+    secrets: SecretStore,
+    loaded: RwLock<Loaded>,
+}
+
+impl SharedState {
+    pub async fn load(secrets: SecretStore) -> Result<Self, Error> {
+        let state = State::load(&secrets).await?;
+        Ok(Self {
+            secrets,
+            loaded: RwLock::new(Loaded {
+                state: Arc::new(state),
+                attempted_at: Instant::now(),
+            }),
+        })
+    }
+
+    fn is_due(&self, loaded: &Loaded) -> bool {
+        self.secrets
+            .refresh_interval()
+            .is_some_and(|interval| loaded.attempted_at.elapsed() >= interval)
+    }
+
+    /// Reloads the state if the refresh interval elapsed, keeps the previous state if that fails
+    pub async fn current(&self) -> Arc<State> {
+        {
+            let loaded = self.loaded.read().await;
+            if !self.is_due(&loaded) {
+                return loaded.state.clone();
+            }
+        }
+
+        let mut loaded = self.loaded.write().await;
+        // another request reloaded in the meantime
+        if !self.is_due(&loaded) {
+            return loaded.state.clone();
+        }
+
+        match loaded.state.reload(&self.secrets).await {
+            Ok(fresh) => {
+                tracing::info!("Refreshed secrets");
+                loaded.state = Arc::new(fresh);
+            }
+            Err(error) => {
+                tracing::error!(?error, "Failed to refresh secrets, keeping previous values");
+            }
+        }
+        loaded.attempted_at = Instant::now();
+        loaded.state.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::{BoxFuture, Env, SecretSource};
+    use crate::types::Secret;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    const REFRESH_INTERVAL: Duration = Duration::from_millis(200);
+
+    /// Only the first fetch succeeds
+    #[derive(Clone, Default)]
+    struct FailingSource(Arc<AtomicUsize>);
+
+    impl SecretSource for FailingSource {
+        fn fetch<'a>(
+            &'a self,
+            _: &'a Env,
+            _: &'a str,
+            _: &'a str,
+        ) -> BoxFuture<'a, Result<Secret, Error>> {
+            Box::pin(async move {
+                match self.0.fetch_add(1, Ordering::SeqCst) {
+                    0 => Ok(Secret::from("It's a Secret to Everybody".to_string())),
+                    _ => Err(Error::Internal("secrets unavailable".to_string())),
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_refresh_is_retried_after_the_interval() {
+        let source = FailingSource::default();
+        let secrets = SecretStore::new(Some(REFRESH_INTERVAL))
+            .with_env(Env::from_iter([
+                ("WEBHOOK_SECRET_FAKE", "webhook-secret"),
+                ("JIRA_BASE_URL", "https://example.atlassian.net"),
+                ("JIRA_USER_EMAIL", "bot@example.com"),
+                ("JIRA_API_TOKEN", "token"),
+            ]))
+            .with_source("_FAKE", source.clone());
+        let shared = SharedState::load(secrets).await.unwrap();
+        let initial = shared.current().await;
+
+        tokio::time::sleep(REFRESH_INTERVAL).await;
+        for _ in 0..3 {
+            assert!(Arc::ptr_eq(&shared.current().await, &initial));
+        }
+        assert_eq!(source.0.load(Ordering::SeqCst), 2);
+
+        tokio::time::sleep(REFRESH_INTERVAL).await;
+        shared.current().await;
+        assert_eq!(source.0.load(Ordering::SeqCst), 3);
+    }
+}
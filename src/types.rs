@@ -1,19 +1,153 @@
 use crate::error::Error;
+use std::collections::BTreeMap;
+use std::fmt;
+use zeroize::Zeroize;
 
+/// Credential that is redacted when formatted and wiped from memory when dropped,
+/// the value is only available through [`Secret::expose`]
 #[derive(Clone)]
-pub struct WebhookSecret(Vec<u8>);
+pub struct Secret<T: Zeroize = String>(T);
+
+impl<T: Zeroize> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl<T: Zeroize> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WebhookSecret(Secret<Vec<u8>>);
 
 impl WebhookSecret {
     pub fn new(secret: impl Into<Vec<u8>>) -> Result<Self, Error> {
-        let secret = secret.into();
-        if secret.is_empty() {
+        let secret = Secret::new(secret.into());
+        if secret.expose().is_empty() {
             return Err(Error::EmptyWebhookSecret);
         }
         Ok(Self(secret))
     }
 
+    pub fn from_secret(secret: Secret) -> Result<Self, Error> {
+        Self::new(secret.expose().as_bytes())
+    }
+
     pub fn as_bytes(&self) -> &[u8] {
-        self.0.as_slice()
+        self.0.expose()
+    }
+}
+
+/// Secrets accepted for webhook signatures, the first is current and the others are deprecated
+/// but still accepted while the secret of the GitHub hook is rotated
+#[derive(Clone, Debug)]
+pub struct WebhookSecrets(Vec<WebhookSecret>);
+
+impl WebhookSecrets {
+    pub fn new(secrets: Vec<WebhookSecret>) -> Result<Self, Error> {
+        if secrets.is_empty() {
+            return Err(Error::EmptyWebhookSecret);
+        }
+        Ok(Self(secrets))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &WebhookSecret> {
+        self.0.iter()
+    }
+
+    /// The secret the GitHub hook signs with
+    pub fn current(&self) -> &WebhookSecret {
+        &self.0[0]
+    }
+}
+
+impl From<WebhookSecret> for WebhookSecrets {
+    fn from(secret: WebhookSecret) -> Self {
+        Self(vec![secret])
+    }
+}
+
+/// Webhook secrets by repository full name or owner, with an optional default for other origins
+#[derive(Clone, Debug, Default)]
+pub struct WebhookSecretMap {
+    default: Option<WebhookSecrets>,
+    by_origin: BTreeMap<String, WebhookSecrets>,
+}
+
+impl WebhookSecretMap {
+    pub fn new(default: Option<WebhookSecrets>) -> Self {
+        Self {
+            default,
+            by_origin: BTreeMap::new(),
+        }
+    }
+
+    /// `origin` is either a repository full name or the login of an organization or user
+    pub fn insert(&mut self, origin: &str, secrets: WebhookSecrets) {
+        self.by_origin.insert(origin.to_lowercase(), secrets);
+    }
+
+    /// Whether the origin of a delivery is needed to select its secrets
+    pub fn is_keyed(&self) -> bool {
+        !self.by_origin.is_empty()
+    }
+
+    /// The secrets of the repository, else of its owner or the organization, else the default
+    pub fn select(
+        &self,
+        repository: Option<&str>,
+        organization: Option<&str>,
+    ) -> Result<&WebhookSecrets, Error> {
+        let owner = repository
+            .and_then(|repository| repository.split_once('/'))
+            .map(|(owner, _)| owner)
+            .or(organization);
+
+        [repository, owner]
+            .into_iter()
+            .flatten()
+            .find_map(|origin| self.by_origin.get(&origin.to_lowercase()))
+            .or(self.default.as_ref())
+            .ok_or_else(|| {
+                Error::NoWebhookSecret(repository.or(organization).unwrap_or("unknown").to_string())
+            })
+    }
+}
+
+impl From<WebhookSecret> for WebhookSecretMap {
+    fn from(secret: WebhookSecret) -> Self {
+        Self::new(Some(secret.into()))
     }
 }
 
@@ -25,6 +159,7 @@ pub enum WebhookEventType {
 }
 
 impl WebhookEventType {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(event_type: &str) -> Self {
         match event_type {
             "ping" => Self::Ping,
@@ -33,3 +168,57 @@ impl WebhookEventType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secrets(secret: &str) -> WebhookSecrets {
+        WebhookSecret::new(secret).unwrap().into()
+    }
+
+    fn selected(
+        map: &WebhookSecretMap,
+        repository: Option<&str>,
+        organization: Option<&str>,
+    ) -> Vec<u8> {
+        let secrets = map.select(repository, organization).unwrap();
+        secrets.iter().next().unwrap().as_bytes().to_vec()
+    }
+
+    #[test]
+    fn secret_is_redacted() {
+        let secret = Secret::from("api-token");
+
+        assert_eq!(format!("{secret:?} {secret}"), "[REDACTED] [REDACTED]");
+        assert_eq!(secret.expose(), "api-token");
+        assert_eq!(
+            format!("{:?}", WebhookSecret::new("hook-secret").unwrap()),
+            "WebhookSecret([REDACTED])"
+        );
+    }
+
+    #[test]
+    fn selects_secret_by_repository_then_owner() {
+        let mut map = WebhookSecretMap::new(Some(secrets("default")));
+        map.insert("example-org", secrets("org"));
+        map.insert("Example-Org/Legacy", secrets("legacy"));
+
+        assert_eq!(selected(&map, Some("example-org/legacy"), None), b"legacy");
+        assert_eq!(selected(&map, Some("example-org/widgets"), None), b"org");
+        assert_eq!(selected(&map, None, Some("Example-Org")), b"org");
+        assert_eq!(selected(&map, Some("other-org/widgets"), None), b"default");
+    }
+
+    #[test]
+    fn missing_mapping_is_rejected_without_default() {
+        let mut map = WebhookSecretMap::default();
+        map.insert("example-org", secrets("org"));
+
+        let error = map
+            .select(Some("other-org/widgets"), None)
+            .expect_err("Expected error");
+
+        assert!(matches!(error, Error::NoWebhookSecret(origin) if origin == "other-org/widgets"));
+    }
+}